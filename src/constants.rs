@@ -0,0 +1,2 @@
+// Number of milliseconds in a (365 days) year, used to annualize yield rate changes
+pub const YEAR_MS: u64 = 365 * 24 * 60 * 60 * 1000;
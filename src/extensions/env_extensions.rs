@@ -2,21 +2,52 @@
 use soroban_sdk::storage::{Instance, Temporary};
 use soroban_sdk::{Address, Env, Symbol, Vec, panic_with_error};
 
-use crate::{PriceOracleContractClient, extensions};
+use crate::{PriceOracleContractClient, constants::YEAR_MS, extensions};
 use crate::types;
 
+use extensions::i128_extensions::mul_div;
 use extensions::u128_helper::U128Helper;
-use types::{asset::Asset, error::Error};
+use extensions::u64_extensions::U64Extensions;
+use types::{asset::Asset, error::Error, fx_guard::FxGuard, fx_mode::FxMode, fx_source::FxSource, price_band::PriceBand, rounding::Rounding, validation_policy::ValidationPolicy};
+// Final price, fx price used for it, the fx price reported by the oracle if it was rejected by the fx guard and
+// the oracle fx and base prices the fx price was converted from
+pub type ValidatedPrice = (i128, i128, Option<i128>, Option<(i128, i128)>);
+
 const ADMIN_KEY: &str = "admin";
 const LAST_TIMESTAMP: &str = "last_timestamp";
 const RETENTION_PERIOD: &str = "period";
 const ASSETS: &str = "assets";
 const BASE_ASSET: &str = "base_asset";
 const DECIMALS: &str = "decimals";
+const YIELD_DECIMALS: &str = "yield_decimals";
 const RESOLUTION: &str = "resolution";
 const FXS: &str = "fxs";
 const FX_ORACLE_ADDRESS: &str = "fx_oracle_address";
+const FX_DECIMALS: &str = "fx_decimals";
+const FX_RESOLUTION: &str = "fx_resolution";
+const FX_MAX_AGE: &str = "fx_max_age";
+const FX_MODE: &str = "fx_mode";
+const FX_ROUTE: &str = "fx_route";
+const FX_SOURCE: &str = "fx_source";
+const FX_BASE: &str = "fx_base";
+const MAX_FX_ROUTE_LEGS: u32 = 3;
+// Legacy maximum yield rate increase in percent, superseded by MAX_YIELD_INCREASE
 const MAX_YIELD_DEVIATION: &str = "max_yield_deviation";
+const MAX_YIELD_INCREASE: &str = "max_yield_increase";
+const MAX_YIELD_DECREASE: &str = "max_yield_decrease";
+// The yield rate decrease allowed before the decrease limit became configurable (1%)
+const DEFAULT_MAX_YIELD_DECREASE_BPS: u32 = 100;
+const MAX_APR: &str = "max_apr";
+// The yield rate growth allowed for assets without a policy, as an APR in basis points (100%)
+const DEFAULT_MAX_APR_BPS: u32 = 10_000;
+const APR_DECIMALS: &str = "apr_decimals";
+const ASSET_APR: &str = "asset_apr";
+const ASSET_POLICY: &str = "asset_policy";
+const ASSET_PRICE_BAND: &str = "asset_band";
+const FX_GUARD: &str = "fx_guard";
+const FX_LATEST_PRICE: &str = "fx_latest_price";
+const ASSET_LATEST_YIELD_RATE: &str = "asset_latest_rate";
+const SKIP_INVALID_UPDATES: &str = "skip_invalid";
 
 pub trait EnvExtensions {
     fn get_admin(&self) -> Option<Address>;
@@ -31,6 +62,10 @@ pub trait EnvExtensions {
 
     fn set_decimals(&self, decimals: u32);
 
+    fn get_yield_decimals(&self) -> u32;
+
+    fn set_yield_decimals(&self, decimals: u32);
+
     fn get_resolution(&self) -> u32;
 
     fn set_resolution(&self, resolution: u32);
@@ -41,7 +76,19 @@ pub trait EnvExtensions {
 
     fn get_price(&self, asset: u8, timestamp: u64) -> Option<i128>;
 
-    fn set_price(&self, asset: u8, fx: Symbol, price: i128, timestamp: u64, ledgers: u32);
+    fn validate_price(&self, asset: u8, fx: &Symbol, yield_rate: i128, timestamp: u64) -> Result<ValidatedPrice, Error>;
+
+    fn store_price(&self, asset: u8, fx: Symbol, yield_rate: i128, validated: ValidatedPrice, timestamp: u64, ledgers: u32);
+
+    fn reject_price(&self, asset: u8, yield_rate: i128, error_code: u32, timestamp: u64, ledgers: u32);
+
+    fn publish_rejection(&self, asset: u8, yield_rate: i128, error_code: u32, timestamp: u64);
+
+    fn correct_price(&self, asset: u8, fx: Symbol, yield_rate: i128, timestamp: u64, ledgers_to_live: u32) -> Result<(i128, i128), Error>;
+
+    fn get_superseded_yield_rate(&self, asset: u8, timestamp: u64) -> Option<i128>;
+
+    fn backfill_prices(&self, asset: u8, records: &Vec<(u64, i128, i128)>) -> Result<(), Error>;
 
     fn get_last_timestamp(&self) -> u64;
 
@@ -73,13 +120,101 @@ pub trait EnvExtensions {
 
     fn set_fx_oracle_address(&self, address: &Address);
 
-    fn get_max_yield_deviation(&self) -> u32;
+    fn get_fx_base(&self) -> Asset;
+
+    fn set_fx_base(&self, base: &Asset);
+
+    fn get_fx_source(&self, fx: u8) -> FxSource;
+
+    fn set_fx_source(&self, fx: u8, source: &FxSource);
+
+    fn get_fx_decimals(&self) -> u32;
+
+    fn set_fx_decimals(&self, decimals: u32);
+
+    fn get_fx_resolution(&self) -> u32;
+
+    fn set_fx_resolution(&self, resolution: u32);
+
+    fn get_fx_max_age(&self, fx: u8) -> u64;
+
+    fn set_fx_max_age(&self, fx: u8, max_age: u64);
+
+    fn get_fx_mode(&self, fx: u8) -> FxMode;
+
+    fn set_fx_mode(&self, fx: u8, mode: &FxMode);
+
+    fn obtain_fx_timestamp(&self, fx: &Symbol) -> Option<u64>;
+
+    fn get_fx_route(&self, fx: u8) -> Vec<Symbol>;
+
+    fn set_fx_route(&self, fx: u8, route: &Vec<Symbol>) -> Result<(), Error>;
+
+    fn get_max_yield_increase(&self) -> u32;
 
-    fn set_max_yield_deviation(&self, percent: u32);
+    fn set_max_yield_increase(&self, bps: u32);
+
+    fn get_max_yield_decrease(&self) -> u32;
+
+    fn set_max_yield_decrease(&self, bps: u32);
+
+    fn get_max_apr(&self) -> u32;
+
+    fn set_max_apr(&self, bps: u32);
+
+    fn get_apr_decimals(&self) -> u32;
+
+    fn set_apr_decimals(&self, decimals: u32);
+
+    fn get_asset_policy(&self, asset: u8) -> ValidationPolicy;
+
+    fn set_asset_policy(&self, asset: u8, policy: &ValidationPolicy);
+
+    fn get_price_band(&self, asset: u8) -> Option<PriceBand>;
+
+    fn set_price_band(&self, asset: u8, band: &PriceBand);
+
+    fn get_asset_apr(&self, asset: u8) -> Option<u32>;
+
+    fn set_asset_apr(&self, asset: u8, apr: u32);
 
     fn get_last_yield_rate(&self, asset: u8, timestamp: u64) -> Option<i128>;
 
     fn set_last_yield_rate(&self, asset: u8, timestamp: u64, yield_rate: i128, ledgers: u32);
+
+    fn get_latest_yield_rate(&self, asset: u8) -> Option<(u64, i128)>;
+
+    fn set_latest_yield_rate(&self, asset: u8, timestamp: u64, yield_rate: i128);
+
+    fn get_skip_invalid_updates(&self) -> bool;
+
+    fn set_skip_invalid_updates(&self, enabled: bool);
+
+    fn get_rejection(&self, asset: u8, timestamp: u64) -> Option<u32>;
+
+    fn set_rejection(&self, asset: u8, timestamp: u64, error_code: u32, ledgers: u32);
+
+    fn get_fx_price(&self, fx: u8, timestamp: u64) -> Option<i128>;
+
+    fn set_fx_price(&self, fx: u8, timestamp: u64, fx_price: i128, ledgers: u32);
+
+    fn obtain_fx_price(&self, fx: &Symbol, timestamp: u64) -> Option<i128>;
+
+    fn get_latest_fx_price(&self, fx: u8) -> Option<(u64, i128, i128)>;
+
+    fn set_latest_fx_price(&self, fx: u8, timestamp: u64, fx_price: i128, oracle_fx_price: i128);
+
+    fn get_rejected_fx_price(&self, fx: u8, timestamp: u64) -> Option<i128>;
+
+    fn set_rejected_fx_price(&self, fx: u8, timestamp: u64, fx_price: i128, ledgers: u32);
+
+    fn get_fx_oracle_prices(&self, fx: u8, timestamp: u64) -> Option<(i128, i128)>;
+
+    fn set_fx_oracle_prices(&self, fx: u8, timestamp: u64, oracle_prices: (i128, i128), ledgers: u32);
+
+    fn get_fx_guard(&self, fx: u8) -> Option<FxGuard>;
+
+    fn set_fx_guard(&self, fx: u8, guard: &FxGuard);
 }
 
 impl EnvExtensions for Env {
@@ -111,6 +246,17 @@ impl EnvExtensions for Env {
         get_instance_storage(&self).set(&DECIMALS, &decimals)
     }
 
+    fn get_yield_decimals(&self) -> u32 {
+        // Contracts configured before yield decimals were introduced use the price decimals
+        get_instance_storage(self)
+            .get(&YIELD_DECIMALS)
+            .unwrap_or_else(|| self.get_decimals())
+    }
+
+    fn set_yield_decimals(&self, decimals: u32) {
+        get_instance_storage(self).set(&YIELD_DECIMALS, &decimals)
+    }
+
     fn get_resolution(&self) -> u32 {
         get_instance_storage(self).get(&RESOLUTION).unwrap()
     }
@@ -136,81 +282,71 @@ impl EnvExtensions for Env {
         get_temporary_storage(self).get(&data_key)
     }
 
-    fn set_price(&self, asset: u8, fx: Symbol, yield_rate: i128, timestamp: u64, ledgers_to_live: u32) {
-        //validate yield_rate >= 1.0 (with matching decimals)
-        let decimals = self.get_decimals();
-        let min_yield_rate =  match 10i128.checked_pow(decimals) {  
-            Some(val) => val,
-            None => panic_with_error!(self, Error::IntegerOverflow),
-        };
-        if yield_rate < min_yield_rate {
-            panic_with_error!(self, Error::InvalidYieldRate);
+    fn validate_price(&self, asset: u8, fx: &Symbol, yield_rate: i128, timestamp: u64) -> Result<ValidatedPrice, Error> {
+        //validate yield_rate is within the policy bounds
+        let policy = self.get_asset_policy(asset);
+        if yield_rate < policy.min_yield_rate || yield_rate > policy.max_yield_rate {
+            return Err(Error::InvalidYieldRate);
         }
 
-        // Retrieve the last yield rate for this asset from the previous timestamp (if it exists)
-        // But only up to the last two resolution cycles
-        let last_timestamp = self.obtain_record_timestamp();
-        let previous_yield_rate = if last_timestamp > 0 {
-            self.get_last_yield_rate(asset, last_timestamp)
-        } else {
-            None
-        };
-        
-        if let Some(prev_rate) = previous_yield_rate {
-            // Monotonic check: allow yield rate to decrease by up to 1%
-            // This is needed because the underlying interest-bearing algorithm can cause slight drops
-            if yield_rate < prev_rate {
-                // Calculate the percentage drop: ((prev_rate - yield_rate) / prev_rate) * 100
-                let drop = match prev_rate.checked_sub(yield_rate) {
-                    Some(val) => val,
-                    None => panic_with_error!(self, Error::IntegerOverflow),
-                };
-                let drop_times_100 = match drop.checked_mul(100) {
-                    Some(val) => val,
-                    None => panic_with_error!(self, Error::IntegerOverflow),
-                };
-                let percentage_drop = match drop_times_100.checked_div(prev_rate) {
-                    Some(val) => val,
-                    None => panic_with_error!(self, Error::IntegerOverflow),
-                };
-                
-                // If drop is more than 1%, reject it
-                if percentage_drop > 1 {
-                    panic_with_error!(self, Error::YieldRateDecreased);
-                }
-            }
-            
-            // Deviation check: calculate absolute percentage change
-            // Formula: (new - old) / old * 100
-            let change = match yield_rate.checked_sub(prev_rate) {
-                Some(val) => val,
-                None => panic_with_error!(self, Error::IntegerOverflow),
-            };
-            
-            // Use checked operations to prevent overflow
-            let change_times_100 = match change.checked_mul(100) {
-                Some(val) => val,
-                None => panic_with_error!(self, Error::IntegerOverflow),
-            };
-            
-            let percentage_change = match change_times_100.checked_div(prev_rate) {
-                Some(val) => val,
-                None => panic_with_error!(self, Error::IntegerOverflow),
-            };
-            
-            let max_deviation = self.get_max_yield_deviation() as i128;
-            if percentage_change > max_deviation {
-                panic_with_error!(self, Error::YieldRateDeviationExceeded);
-            }
+        // Retrieve the most recent yield rate stored for this asset, even if it was recorded long ago
+        let latest = self.get_latest_yield_rate(asset);
+        if let Some((prev_timestamp, prev_rate)) = latest {
+            check_yield_rate_change(&policy, prev_timestamp, prev_rate, timestamp, yield_rate)?;
         }
-        
+
+        let (oracle_fx_price, base_price) = get_fx_source_prices(self, fx, timestamp)?;
+        let fx_price = match base_price {
+            Some(base_price) => convert_to_contract_base(self, oracle_fx_price, base_price)?,
+            None => oracle_fx_price,
+        };
+        let fx_index = self.get_fx_index(fx).ok_or(Error::FxMissing)?;
+        // Baselines older than the fx max age are ignored, so a long gap doesn't pin the fx price
+        let max_age = self.get_fx_max_age(fx_index);
+        let prev_fx_prices = self
+            .get_latest_fx_price(fx_index)
+            .filter(|(prev_timestamp, _, _)| prev_timestamp.saturating_add(max_age) >= timestamp)
+            .map(|(_, prev_fx_price, prev_oracle_fx_price)| (prev_fx_price, prev_oracle_fx_price));
+        let (fx_price, rejected_fx_price) = check_fx_price_change(self, fx_index, prev_fx_prices, fx_price)?;
+        // A clamped fx price is already in the contract base, so there is nothing to convert it from
+        let oracle_prices = base_price
+            .filter(|_| rejected_fx_price.is_none())
+            .map(|base_price| (oracle_fx_price, base_price));
+        let price = get_record_price(self, yield_rate, fx_price, oracle_prices)?;
+        let prev_price = latest.and_then(|(prev_timestamp, _)| self.get_price(asset, prev_timestamp));
+        check_final_price(self, asset, prev_price, price)?;
+        Ok((price, fx_price, rejected_fx_price, oracle_prices))
+    }
+
+    fn store_price(&self, asset: u8, fx: Symbol, yield_rate: i128, validated: ValidatedPrice, timestamp: u64, ledgers_to_live: u32) {
+        let (price, fx_price, rejected_fx_price, oracle_prices) = validated;
+
         // Store the new yield rate for future comparisons
         self.set_last_yield_rate(asset, timestamp, yield_rate, ledgers_to_live);
+        self.set_latest_yield_rate(asset, timestamp, yield_rate);
+
+        // Store the fx price used for this tick, so prices can be quoted in the fx currency later
+        let fx_index = self.get_fx_index(&fx).unwrap();
+        self.set_fx_price(fx_index, timestamp, fx_price, ledgers_to_live);
+        if let Some(oracle_prices) = oracle_prices {
+            // Keep the prices the fx price was converted from, so corrections apply the yield the same way
+            self.set_fx_oracle_prices(fx_index, timestamp, oracle_prices, ledgers_to_live);
+        }
+        // Keep the oracle print as well, so the next tick can confirm a real move
+        self.set_latest_fx_price(fx_index, timestamp, fx_price, rejected_fx_price.unwrap_or(fx_price));
+        if let Some(rejected_fx_price) = rejected_fx_price {
+            // Flag the tick, keeping the fx price reported by the oracle for inspection
+            if self.get_rejected_fx_price(fx_index, timestamp).is_none() {
+                self.set_rejected_fx_price(fx_index, timestamp, rejected_fx_price, ledgers_to_live);
+                self.events().publish(
+                    (Symbol::new(self, "fx_price_clamped"), fx),
+                    (timestamp, rejected_fx_price, fx_price),
+                );
+            }
+        }
 
         //build the key for the price
         let data_key = U128Helper::encode_record_key(timestamp, asset);
-        let fx_price = get_reflector_fx_price(self, fx, timestamp);
-        let price = get_price_with_yield(self, yield_rate, fx_price, decimals);
 
         //set the price
         let temps_storage = get_temporary_storage(&self);
@@ -221,6 +357,196 @@ impl EnvExtensions for Env {
         }
     }
 
+    fn reject_price(&self, asset: u8, yield_rate: i128, error_code: u32, timestamp: u64, ledgers_to_live: u32) {
+        //mark the asset as rejected for this tick, so the failure can be inspected later
+        self.set_rejection(asset, timestamp, error_code, ledgers_to_live);
+        self.publish_rejection(asset, yield_rate, error_code, timestamp);
+    }
+
+    fn publish_rejection(&self, asset: u8, yield_rate: i128, error_code: u32, timestamp: u64) {
+        self.events().publish(
+            (Symbol::new(self, "price_rejected"), asset as u32),
+            (timestamp, error_code),
+        );
+        if error_code == Error::YieldRateDecreased as u32 || error_code == Error::YieldRateDeviationExceeded as u32 {
+            // Report the exact deviation of the rejected yield rate
+            publish_yield_rate_rejected(self, asset, yield_rate);
+        }
+    }
+
+    fn correct_price(&self, asset: u8, fx: Symbol, yield_rate: i128, timestamp: u64, ledgers_to_live: u32) -> Result<(i128, i128), Error> {
+        // Only already published records can be corrected
+        let superseded_rate = self.get_last_yield_rate(asset, timestamp).ok_or(Error::RecordMissing)?;
+        // Keep the originally published rate, so a record can be corrected only once
+        if self.get_superseded_yield_rate(asset, timestamp).is_some() {
+            return Err(Error::RecordAlreadyCorrected);
+        }
+        let policy = self.get_asset_policy(asset);
+        if yield_rate < policy.min_yield_rate || yield_rate > policy.max_yield_rate {
+            return Err(Error::InvalidYieldRate);
+        }
+
+        // Validate the corrected rate against the nearest stored records instead of the latest record
+        let prev = get_nearest_yield_rate(self, asset, timestamp, false);
+        if let Some((prev_timestamp, prev_rate)) = prev {
+            check_yield_rate_change(&policy, prev_timestamp, prev_rate, timestamp, yield_rate)?;
+        }
+        let next = get_nearest_yield_rate(self, asset, timestamp, true);
+        if let Some((next_timestamp, next_rate)) = next {
+            check_yield_rate_change(&policy, timestamp, yield_rate, next_timestamp, next_rate)?;
+        }
+
+        // Reuse the fx price stored for this tick, so the correction only affects the yield component
+        let fx_index = self.get_fx_index(&fx).unwrap();
+        let stored_fx_price = self.get_fx_price(fx_index, timestamp);
+        let (fx_price, oracle_prices) = match stored_fx_price {
+            Some(fx_price) => (fx_price, self.get_fx_oracle_prices(fx_index, timestamp)),
+            None => {
+                // Guard the oracle price against the previous record, a correction is never clamped
+                let (oracle_fx_price, base_price) = get_fx_source_prices(self, &fx, timestamp)?;
+                let fx_price = match base_price {
+                    Some(base_price) => convert_to_contract_base(self, oracle_fx_price, base_price)?,
+                    None => oracle_fx_price,
+                };
+                let prev_fx_prices = prev
+                    .and_then(|(prev_timestamp, _)| self.get_fx_price(fx_index, prev_timestamp))
+                    .map(|prev_fx_price| (prev_fx_price, prev_fx_price));
+                match check_fx_price_change(self, fx_index, prev_fx_prices, fx_price)? {
+                    (fx_price, None) => (fx_price, base_price.map(|base_price| (oracle_fx_price, base_price))),
+                    (_, Some(_)) => return Err(Error::FxDeviationExceeded),
+                }
+            }
+        };
+        let price = get_record_price(self, yield_rate, fx_price, oracle_prices)?;
+        let prev_price = prev.and_then(|(prev_timestamp, _)| self.get_price(asset, prev_timestamp));
+        check_final_price(self, asset, prev_price, price)?;
+        if let Some(next_price) = next.and_then(|(next_timestamp, _)| self.get_price(asset, next_timestamp)) {
+            check_price_change(self, asset, price, next_price)?;
+        }
+
+        // Keep the superseded yield rate for auditing, using bit 11 to distinguish it from other records
+        let superseded_key = U128Helper::encode_record_key(timestamp, asset) | (1u128 << 11);
+        let temps_storage = get_temporary_storage(self);
+        temps_storage.set(&superseded_key, &superseded_rate);
+        if ledgers_to_live > 16 {
+            temps_storage.extend_ttl(&superseded_key, ledgers_to_live, ledgers_to_live);
+        }
+
+        self.set_last_yield_rate(asset, timestamp, yield_rate, ledgers_to_live);
+        if stored_fx_price.is_none() {
+            self.set_fx_price(fx_index, timestamp, fx_price, ledgers_to_live);
+            if let Some(oracle_prices) = oracle_prices {
+                self.set_fx_oracle_prices(fx_index, timestamp, oracle_prices, ledgers_to_live);
+            }
+        }
+        if let Some((latest_timestamp, _)) = self.get_latest_yield_rate(asset) {
+            if latest_timestamp == timestamp {
+                self.set_latest_yield_rate(asset, timestamp, yield_rate);
+            }
+        }
+
+        let data_key = U128Helper::encode_record_key(timestamp, asset);
+        temps_storage.set(&data_key, &price);
+        if ledgers_to_live > 16 {
+            temps_storage.extend_ttl(&data_key, ledgers_to_live, ledgers_to_live)
+        }
+        Ok((price, superseded_rate))
+    }
+
+    fn backfill_prices(&self, asset: u8, records: &Vec<(u64, i128, i128)>) -> Result<(), Error> {
+        if records.is_empty() || records.len() >= 256 {
+            return Err(Error::InvalidUpdateLength);
+        }
+        let policy = self.get_asset_policy(asset);
+        let resolution: u64 = self.get_resolution().into();
+        let retention_period = self.get_retention_period();
+        let now = self.ledger().timestamp() * 1000;
+        let last_timestamp = self.get_last_timestamp();
+
+        // Validate the whole series before writing anything
+        let mut prev: Option<(u64, i128, i128)> = None;
+        for (timestamp, yield_rate, fx_price) in records.iter() {
+            // Only past ticks within the retention period can be backfilled
+            if timestamp == 0
+                || !timestamp.is_valid_timestamp(resolution)
+                || timestamp > last_timestamp
+                || timestamp + retention_period <= now
+            {
+                return Err(Error::InvalidTimestamp);
+            }
+            if self.get_last_yield_rate(asset, timestamp).is_some() || self.get_price(asset, timestamp).is_some() {
+                return Err(Error::RecordAlreadyExists);
+            }
+            if yield_rate < policy.min_yield_rate || yield_rate > policy.max_yield_rate {
+                return Err(Error::InvalidYieldRate);
+            }
+            // Supplied fx prices are already in the contract decimals and base asset, so they are not rescaled
+            if fx_price <= 0 {
+                return Err(Error::InvalidFxPrice);
+            }
+            // The series must be ordered and consistent with the nearest stored record preceding each supplied one
+            let stored_prev = get_nearest_yield_rate(self, asset, timestamp, false)
+                .map(|(prev_timestamp, prev_rate)| (prev_timestamp, prev_rate, self.get_price(asset, prev_timestamp)));
+            let prev_record = match (prev, stored_prev) {
+                (Some((prev_timestamp, _, _)), _) if prev_timestamp >= timestamp => return Err(Error::InvalidTimestamp),
+                (Some((prev_timestamp, prev_rate, prev_price)), Some((stored_timestamp, stored_rate, stored_price)))
+                    if stored_timestamp > prev_timestamp =>
+                {
+                    // Stored records lie between the supplied ones, so the first of them must follow the previous
+                    // supplied record, and the last one must precede the current supplied record
+                    if let Some((next_timestamp, next_rate)) = get_nearest_yield_rate(self, asset, prev_timestamp, true) {
+                        check_yield_rate_change(&policy, prev_timestamp, prev_rate, next_timestamp, next_rate)?;
+                        if let Some(next_price) = self.get_price(asset, next_timestamp) {
+                            check_price_change(self, asset, prev_price, next_price)?;
+                        }
+                    }
+                    Some((stored_timestamp, stored_rate, stored_price))
+                }
+                (Some((prev_timestamp, prev_rate, prev_price)), _) => Some((prev_timestamp, prev_rate, Some(prev_price))),
+                (None, stored_prev) => stored_prev,
+            };
+            if let Some((prev_timestamp, prev_rate, _)) = prev_record {
+                check_yield_rate_change(&policy, prev_timestamp, prev_rate, timestamp, yield_rate)?;
+            }
+            // The final price goes through the same band checks as the live updates
+            let price = get_record_price(self, yield_rate, fx_price, None)?;
+            check_final_price(self, asset, prev_record.and_then(|(_, _, prev_price)| prev_price), price)?;
+            prev = Some((timestamp, yield_rate, price));
+        }
+        // The last supplied record must be consistent with the nearest stored record following it
+        if let Some((timestamp, yield_rate, price)) = prev {
+            if let Some((next_timestamp, next_rate)) = get_nearest_yield_rate(self, asset, timestamp, true) {
+                check_yield_rate_change(&policy, timestamp, yield_rate, next_timestamp, next_rate)?;
+                if let Some(next_price) = self.get_price(asset, next_timestamp) {
+                    check_price_change(self, asset, price, next_price)?;
+                }
+            }
+        }
+
+        let fx_index = self.get_fx_index(&self.get_fxs().get(asset as u32).unwrap()).unwrap();
+        let temps_storage = get_temporary_storage(self);
+        for (timestamp, yield_rate, fx_price) in records.iter() {
+            let price = get_record_price(self, yield_rate, fx_price, None)?;
+            // Backfilled records expire together with the live records of the same tick
+            let ledgers_to_live = (((timestamp + retention_period - now) / 1000 / 5) + 1) as u32;
+            self.set_last_yield_rate(asset, timestamp, yield_rate, ledgers_to_live);
+            self.set_latest_yield_rate(asset, timestamp, yield_rate);
+            self.set_fx_price(fx_index, timestamp, fx_price, ledgers_to_live);
+
+            let data_key = U128Helper::encode_record_key(timestamp, asset);
+            temps_storage.set(&data_key, &price);
+            if ledgers_to_live > 16 {
+                temps_storage.extend_ttl(&data_key, ledgers_to_live, ledgers_to_live)
+            }
+        }
+        Ok(())
+    }
+
+    fn get_superseded_yield_rate(&self, asset: u8, timestamp: u64) -> Option<i128> {
+        let data_key = U128Helper::encode_record_key(timestamp, asset) | (1u128 << 11);
+        get_temporary_storage(self).get(&data_key)
+    }
+
     fn get_last_timestamp(&self) -> u64 {
         //get the marker
         get_instance_storage(&self)
@@ -321,12 +647,202 @@ impl EnvExtensions for Env {
         get_instance_storage(self).set(&FX_ORACLE_ADDRESS, address);
     }
 
-    fn get_max_yield_deviation(&self) -> u32 {
-        get_instance_storage(self).get(&MAX_YIELD_DEVIATION).unwrap_or(0)
+    fn get_fx_base(&self) -> Asset {
+        // Contracts configured before the fx oracle base was tracked assume matching base assets
+        get_instance_storage(self)
+            .get(&FX_BASE)
+            .unwrap_or_else(|| self.get_base_asset())
+    }
+
+    fn set_fx_base(&self, base: &Asset) {
+        get_instance_storage(self).set(&FX_BASE, base);
+    }
+
+    fn get_fx_source(&self, fx: u8) -> FxSource {
+        let source = get_instance_storage(self).get(&(FX_SOURCE, fx as u32));
+        if let Some(source) = source {
+            return source;
+        }
+        // The fx oracle base currency is pegged 1:1 by default, the rest is quoted by the fx oracle
+        let fx_symbol = self.get_fxs().get(fx as u32);
+        match (fx_symbol, self.get_fx_base()) {
+            (Some(fx_symbol), Asset::Other(fx_base)) if fx_symbol == fx_base => {
+                match 10i128.checked_pow(self.get_decimals()) {
+                    Some(rate) => FxSource::Peg(rate),
+                    None => panic_with_error!(self, Error::IntegerOverflow),
+                }
+            }
+            _ => FxSource::Reflector,
+        }
+    }
+
+    fn set_fx_source(&self, fx: u8, source: &FxSource) {
+        get_instance_storage(self).set(&(FX_SOURCE, fx as u32), source);
+    }
+
+    fn get_fx_decimals(&self) -> u32 {
+        // Contracts configured before the fx oracle decimals were tracked assume matching decimals
+        get_instance_storage(self)
+            .get(&FX_DECIMALS)
+            .unwrap_or_else(|| self.get_decimals())
+    }
+
+    fn set_fx_decimals(&self, decimals: u32) {
+        get_instance_storage(self).set(&FX_DECIMALS, &decimals);
+    }
+
+    fn get_fx_resolution(&self) -> u32 {
+        // Contracts configured before the fx oracle resolution was tracked assume matching resolution
+        get_instance_storage(self)
+            .get(&FX_RESOLUTION)
+            .unwrap_or_else(|| self.get_resolution())
+    }
+
+    fn set_fx_resolution(&self, resolution: u32) {
+        get_instance_storage(self).set(&FX_RESOLUTION, &resolution);
+    }
+
+    fn get_fx_max_age(&self, fx: u8) -> u64 {
+        get_instance_storage(self)
+            .get(&(FX_MAX_AGE, fx as u32))
+            .unwrap_or_else(|| get_default_fx_max_age(self))
+    }
+
+    fn set_fx_max_age(&self, fx: u8, max_age: u64) {
+        get_instance_storage(self).set(&(FX_MAX_AGE, fx as u32), &max_age);
+    }
+
+    fn get_fx_mode(&self, fx: u8) -> FxMode {
+        get_instance_storage(self)
+            .get(&(FX_MODE, fx as u32))
+            .unwrap_or(FxMode::Spot)
+    }
+
+    fn set_fx_mode(&self, fx: u8, mode: &FxMode) {
+        get_instance_storage(self).set(&(FX_MODE, fx as u32), mode);
+    }
+
+    fn obtain_fx_timestamp(&self, fx: &Symbol) -> Option<u64> {
+        let reflector_client = get_reflector_oracle(self).ok()?;
+        let fx_index = self.get_fx_index(fx)?;
+        // The oldest leg of the fx route defines the fx price age
+        let mut timestamp: Option<u64> = None;
+        let mut base = fx.clone();
+        for quote in self.get_fx_route(fx_index).iter().map(Some).chain([None]) {
+            let (_, leg_timestamp) =
+                get_reflector_fx_leg(self, &reflector_client, &base, quote.as_ref(), &FxMode::Spot).ok()?;
+            timestamp = Some(timestamp.map_or(leg_timestamp, |val| val.min(leg_timestamp)));
+            if let Some(quote) = quote {
+                base = quote;
+            }
+        }
+        timestamp
     }
 
-    fn set_max_yield_deviation(&self, percent: u32) {
-        get_instance_storage(self).set(&MAX_YIELD_DEVIATION, &percent);
+    fn get_fx_route(&self, fx: u8) -> Vec<Symbol> {
+        get_instance_storage(self)
+            .get(&(FX_ROUTE, fx as u32))
+            .unwrap_or_else(|| Vec::new(self))
+    }
+
+    fn set_fx_route(&self, fx: u8, route: &Vec<Symbol>) -> Result<(), Error> {
+        let fx_symbol = self.get_fxs().get(fx as u32).ok_or(Error::FxMissing)?;
+        // Routes are limited to a few legs, without loops
+        if route.len() > MAX_FX_ROUTE_LEGS {
+            return Err(Error::InvalidFxRoute);
+        }
+        for (i, hop) in route.iter().enumerate() {
+            if hop == fx_symbol || route.first_index_of(&hop) != Some(i as u32) {
+                return Err(Error::InvalidFxRoute);
+            }
+        }
+        // Every leg must be quoted by the fx oracle before the route is stored
+        if get_reflector_route_price(self, &fx_symbol, Some(fx), route, 0).is_err() {
+            return Err(Error::InvalidFxRoute);
+        }
+        get_instance_storage(self).set(&(FX_ROUTE, fx as u32), route);
+        Ok(())
+    }
+
+    fn get_max_yield_increase(&self) -> u32 {
+        let storage = get_instance_storage(self);
+        match storage.get(&MAX_YIELD_INCREASE) {
+            Some(bps) => bps,
+            // Migrate the legacy percent value configured before basis points were introduced
+            None => storage.get::<_, u32>(&MAX_YIELD_DEVIATION).unwrap_or(0) * 100,
+        }
+    }
+
+    fn set_max_yield_increase(&self, bps: u32) {
+        let storage = get_instance_storage(self);
+        storage.set(&MAX_YIELD_INCREASE, &bps);
+        storage.remove(&MAX_YIELD_DEVIATION);
+    }
+
+    fn get_max_yield_decrease(&self) -> u32 {
+        get_instance_storage(self)
+            .get(&MAX_YIELD_DECREASE)
+            .unwrap_or(DEFAULT_MAX_YIELD_DECREASE_BPS)
+    }
+
+    fn set_max_yield_decrease(&self, bps: u32) {
+        get_instance_storage(self).set(&MAX_YIELD_DECREASE, &bps);
+    }
+
+    fn get_max_apr(&self) -> u32 {
+        get_instance_storage(self).get(&MAX_APR).unwrap_or(DEFAULT_MAX_APR_BPS)
+    }
+
+    fn set_max_apr(&self, bps: u32) {
+        get_instance_storage(self).set(&MAX_APR, &bps);
+    }
+
+    fn get_apr_decimals(&self) -> u32 {
+        get_instance_storage(self).get(&APR_DECIMALS).unwrap_or(0)
+    }
+
+    fn set_apr_decimals(&self, decimals: u32) {
+        get_instance_storage(self).set(&APR_DECIMALS, &decimals);
+    }
+
+    fn get_asset_policy(&self, asset: u8) -> ValidationPolicy {
+        let policy = get_instance_storage(self).get(&(ASSET_POLICY, asset as u32));
+        if let Some(policy) = policy {
+            return policy;
+        }
+        // Fall back to the global limits with yield rate >= 1.0 (with yield decimals)
+        let min_yield_rate = match 10i128.checked_pow(self.get_yield_decimals()) {
+            Some(val) => val,
+            None => panic_with_error!(self, Error::IntegerOverflow),
+        };
+        ValidationPolicy {
+            max_increase_bps: self.get_max_yield_increase(),
+            max_decrease_bps: self.get_max_yield_decrease(),
+            min_yield_rate,
+            max_yield_rate: i128::MAX,
+            monotonic: true,
+            max_apr_bps: self.get_max_apr(),
+        }
+    }
+
+    fn set_asset_policy(&self, asset: u8, policy: &ValidationPolicy) {
+        get_instance_storage(self).set(&(ASSET_POLICY, asset as u32), policy);
+    }
+
+    fn get_price_band(&self, asset: u8) -> Option<PriceBand> {
+        get_instance_storage(self).get(&(ASSET_PRICE_BAND, asset as u32))
+    }
+
+    fn set_price_band(&self, asset: u8, band: &PriceBand) {
+        get_instance_storage(self).set(&(ASSET_PRICE_BAND, asset as u32), band);
+    }
+
+    fn get_asset_apr(&self, asset: u8) -> Option<u32> {
+        get_instance_storage(self).get(&(ASSET_APR, asset as u32))
+    }
+
+    fn set_asset_apr(&self, asset: u8, apr: u32) {
+        get_instance_storage(self).set(&(ASSET_APR, asset as u32), &apr);
     }
 
     fn get_last_yield_rate(&self, asset: u8, timestamp: u64) -> Option<i128> {
@@ -348,6 +864,313 @@ impl EnvExtensions for Env {
             temps_storage.extend_ttl(&data_key, ledgers, ledgers);
         }
     }
+
+    fn get_latest_yield_rate(&self, asset: u8) -> Option<(u64, i128)> {
+        let latest = get_instance_storage(self).get(&(ASSET_LATEST_YIELD_RATE, asset as u32));
+        if latest.is_some() {
+            return latest;
+        }
+        // Fall back to the record at the last timestamp for assets updated before the latest yield rate was tracked
+        let last_timestamp = self.get_last_timestamp();
+        if last_timestamp == 0 {
+            return None;
+        }
+        let yield_rate = self.get_last_yield_rate(asset, last_timestamp)?;
+        Some((last_timestamp, yield_rate))
+    }
+
+    fn set_latest_yield_rate(&self, asset: u8, timestamp: u64, yield_rate: i128) {
+        //keep the most recent record only
+        if let Some((latest_timestamp, _)) = self.get_latest_yield_rate(asset) {
+            if latest_timestamp > timestamp {
+                return;
+            }
+        }
+        get_instance_storage(self).set(&(ASSET_LATEST_YIELD_RATE, asset as u32), &(timestamp, yield_rate));
+    }
+
+    fn get_skip_invalid_updates(&self) -> bool {
+        get_instance_storage(self).get(&SKIP_INVALID_UPDATES).unwrap_or(false)
+    }
+
+    fn set_skip_invalid_updates(&self, enabled: bool) {
+        get_instance_storage(self).set(&SKIP_INVALID_UPDATES, &enabled);
+    }
+
+    fn get_rejection(&self, asset: u8, timestamp: u64) -> Option<u32> {
+        // Rejections are stored per asset and timestamp with bit 10 set to distinguish them
+        // from price records (no bits set), yield rate records (bit 8 set) and fx price records (bit 9 set)
+        let data_key = U128Helper::encode_record_key(timestamp, asset) | (1u128 << 10);
+        get_temporary_storage(self).get(&data_key)
+    }
+
+    fn set_rejection(&self, asset: u8, timestamp: u64, error_code: u32, ledgers: u32) {
+        let data_key = U128Helper::encode_record_key(timestamp, asset) | (1u128 << 10);
+        let temps_storage = get_temporary_storage(self);
+        temps_storage.set(&data_key, &error_code);
+        if ledgers > 16 {
+            temps_storage.extend_ttl(&data_key, ledgers, ledgers);
+        }
+    }
+
+    fn get_fx_price(&self, fx: u8, timestamp: u64) -> Option<i128> {
+        // Fx prices are stored per fx and timestamp with bit 9 set to distinguish them
+        // from price records (no bits set) and yield rate records (bit 8 set)
+        let data_key = U128Helper::encode_record_key(timestamp, fx) | (1u128 << 9);
+        get_temporary_storage(self).get(&data_key)
+    }
+
+    fn set_fx_price(&self, fx: u8, timestamp: u64, fx_price: i128, ledgers: u32) {
+        let data_key = U128Helper::encode_record_key(timestamp, fx) | (1u128 << 9);
+        let temps_storage = get_temporary_storage(self);
+        temps_storage.set(&data_key, &fx_price);
+        if ledgers > 16 {
+            temps_storage.extend_ttl(&data_key, ledgers, ledgers);
+        }
+    }
+
+    fn get_latest_fx_price(&self, fx: u8) -> Option<(u64, i128, i128)> {
+        get_instance_storage(self).get(&(FX_LATEST_PRICE, fx as u32))
+    }
+
+    fn set_latest_fx_price(&self, fx: u8, timestamp: u64, fx_price: i128, oracle_fx_price: i128) {
+        //keep the most recent record only
+        if let Some((latest_timestamp, _, _)) = self.get_latest_fx_price(fx) {
+            if latest_timestamp > timestamp {
+                return;
+            }
+        }
+        get_instance_storage(self).set(&(FX_LATEST_PRICE, fx as u32), &(timestamp, fx_price, oracle_fx_price));
+    }
+
+    fn get_rejected_fx_price(&self, fx: u8, timestamp: u64) -> Option<i128> {
+        // Rejected fx prices are stored per fx and timestamp with bit 12 set to distinguish them from other records
+        let data_key = U128Helper::encode_record_key(timestamp, fx) | (1u128 << 12);
+        get_temporary_storage(self).get(&data_key)
+    }
+
+    fn set_rejected_fx_price(&self, fx: u8, timestamp: u64, fx_price: i128, ledgers: u32) {
+        let data_key = U128Helper::encode_record_key(timestamp, fx) | (1u128 << 12);
+        let temps_storage = get_temporary_storage(self);
+        temps_storage.set(&data_key, &fx_price);
+        if ledgers > 16 {
+            temps_storage.extend_ttl(&data_key, ledgers, ledgers);
+        }
+    }
+
+    fn get_fx_oracle_prices(&self, fx: u8, timestamp: u64) -> Option<(i128, i128)> {
+        // Oracle fx and base prices are stored per fx and timestamp with bit 13 set to distinguish them from other records
+        let data_key = U128Helper::encode_record_key(timestamp, fx) | (1u128 << 13);
+        get_temporary_storage(self).get(&data_key)
+    }
+
+    fn set_fx_oracle_prices(&self, fx: u8, timestamp: u64, oracle_prices: (i128, i128), ledgers: u32) {
+        let data_key = U128Helper::encode_record_key(timestamp, fx) | (1u128 << 13);
+        let temps_storage = get_temporary_storage(self);
+        temps_storage.set(&data_key, &oracle_prices);
+        if ledgers > 16 {
+            temps_storage.extend_ttl(&data_key, ledgers, ledgers);
+        }
+    }
+
+    fn get_fx_guard(&self, fx: u8) -> Option<FxGuard> {
+        get_instance_storage(self).get(&(FX_GUARD, fx as u32))
+    }
+
+    fn set_fx_guard(&self, fx: u8, guard: &FxGuard) {
+        get_instance_storage(self).set(&(FX_GUARD, fx as u32), guard);
+    }
+
+    fn obtain_fx_price(&self, fx: &Symbol, timestamp: u64) -> Option<i128> {
+        let base_symbol = get_base_symbol(self);
+        if base_symbol.as_ref() == Some(fx) {
+            return 10i128.checked_pow(self.get_decimals());
+        }
+        let fx_index = self.get_fx_index(fx)?;
+        // Prefer the fx price that was used for the tick
+        let fx_price = self.get_fx_price(fx_index, timestamp);
+        if fx_price.is_some() {
+            return fx_price;
+        }
+        // Fall back to the peg or the FX oracle record for the tick (converted to seconds), manual and routed
+        // fx prices can only be derived for the current tick
+        let fx_price = match self.get_fx_source(fx_index) {
+            FxSource::Peg(rate) => rate,
+            FxSource::Reflector if self.get_fx_route(fx_index).is_empty() => get_reflector_fx_price_at(self, fx, timestamp)?,
+            _ => return None,
+        };
+        match base_symbol {
+            None => Some(fx_price),
+            Some(base_symbol) => {
+                let base_price = get_reflector_fx_price_at(self, &base_symbol, timestamp)?;
+                convert_to_contract_base(self, fx_price, base_price).ok()
+            }
+        }
+    }
+}
+
+pub fn check_yield_rate_change(
+    policy: &ValidationPolicy,
+    prev_timestamp: u64,
+    prev_rate: i128,
+    timestamp: u64,
+    yield_rate: i128,
+) -> Result<(), Error> {
+    // Compare the change relative to the previous rate in basis points without truncation:
+    // |yield_rate - prev_rate| * 10_000 > max_bps * prev_rate
+    let change = yield_rate.checked_sub(prev_rate).ok_or(Error::IntegerOverflow)?;
+    let change_times_10000 = change.abs().checked_mul(10_000).ok_or(Error::IntegerOverflow)?;
+    // Monotonic check: allow yield rate to decrease by up to the configured limit
+    // This is needed because the underlying interest-bearing algorithm can cause slight drops
+    let (max_bps, error) = if change < 0 {
+        (policy.max_decrease_bps, Error::YieldRateDecreased)
+    } else {
+        (policy.max_increase_bps, Error::YieldRateDeviationExceeded)
+    };
+    let max_change = prev_rate.checked_mul(max_bps as i128).ok_or(Error::IntegerOverflow)?;
+    // Decreases are only limited for monotonic assets
+    let is_limited = change >= 0 || policy.monotonic;
+    if is_limited && change_times_10000 > max_change {
+        return Err(error);
+    }
+
+    // APR check: the allowed increase grows with the time elapsed since the previous record, so
+    // gaps in publishing don't loosen the per-record limit. Back-dated records allow no growth:
+    // change * 10_000 * year > max_apr_bps * elapsed * prev_rate
+    if change > 0 && policy.max_apr_bps > 0 {
+        let elapsed = timestamp.saturating_sub(prev_timestamp) as i128;
+        let annualized_change = change_times_10000
+            .checked_mul(YEAR_MS as i128)
+            .ok_or(Error::IntegerOverflow)?;
+        let max_annualized_change = prev_rate
+            .checked_mul(policy.max_apr_bps as i128)
+            .and_then(|val| val.checked_mul(elapsed))
+            .ok_or(Error::IntegerOverflow)?;
+        if annualized_change > max_annualized_change {
+            return Err(Error::YieldRateDeviationExceeded);
+        }
+    }
+    Ok(())
+}
+
+// The fx price is compared with both the previous fx price used and the previous oracle print, so a move
+// confirmed by two consecutive oracle prints is accepted instead of being clamped indefinitely
+fn check_fx_price_change(
+    e: &Env,
+    fx: u8,
+    prev_fx_prices: Option<(i128, i128)>,
+    fx_price: i128,
+) -> Result<(i128, Option<i128>), Error> {
+    let guard = match e.get_fx_guard(fx) {
+        Some(guard) => guard,
+        None => return Ok((fx_price, None)),
+    };
+    let (prev_fx_price, prev_oracle_fx_price) = match prev_fx_prices {
+        Some(prev_fx_prices) => prev_fx_prices,
+        None => return Ok((fx_price, None)),
+    };
+    if is_fx_change_allowed(&guard, prev_fx_price, fx_price)?
+        || is_fx_change_allowed(&guard, prev_oracle_fx_price, fx_price)?
+    {
+        return Ok((fx_price, None));
+    }
+    if !guard.clamp {
+        return Err(Error::FxDeviationExceeded);
+    }
+    // Fall back to the previous fx price, reporting the one provided by the oracle
+    Ok((prev_fx_price, Some(fx_price)))
+}
+
+fn is_fx_change_allowed(guard: &FxGuard, prev_fx_price: i128, fx_price: i128) -> Result<bool, Error> {
+    // |fx_price - prev_fx_price| * 10_000 <= max_change_bps * prev_fx_price
+    let change_times_10000 = fx_price
+        .checked_sub(prev_fx_price)
+        .and_then(|change| change.abs().checked_mul(10_000))
+        .ok_or(Error::IntegerOverflow)?;
+    let max_change = prev_fx_price
+        .checked_mul(guard.max_change_bps as i128)
+        .ok_or(Error::IntegerOverflow)?;
+    Ok(change_times_10000 <= max_change)
+}
+
+// Checks the final price, so a wrong fx leg is caught as well
+fn check_final_price(e: &Env, asset: u8, prev_price: Option<i128>, price: i128) -> Result<(), Error> {
+    if price <= 0 {
+        // A zero price can't be served or used as a cross price quote
+        return Err(Error::NonPositivePrice);
+    }
+    if let Some(band) = e.get_price_band(asset) {
+        if price < band.min_price || price > band.max_price {
+            return Err(Error::PriceOutOfBand);
+        }
+    }
+    match prev_price {
+        Some(prev_price) => check_price_change(e, asset, prev_price, price),
+        None => Ok(()),
+    }
+}
+
+// Checks the tick-over-tick change of the final price against the asset price band
+fn check_price_change(e: &Env, asset: u8, prev_price: i128, price: i128) -> Result<(), Error> {
+    let band = match e.get_price_band(asset) {
+        Some(band) if band.max_change_bps > 0 => band,
+        _ => return Ok(()),
+    };
+    // |price - prev_price| * 10_000 > max_change_bps * prev_price
+    let change_times_10000 = price
+        .checked_sub(prev_price)
+        .and_then(|change| change.abs().checked_mul(10_000))
+        .ok_or(Error::IntegerOverflow)?;
+    let max_change = prev_price
+        .checked_mul(band.max_change_bps as i128)
+        .ok_or(Error::IntegerOverflow)?;
+    if change_times_10000 > max_change {
+        return Err(Error::PriceDeviationExceeded);
+    }
+    Ok(())
+}
+
+// Finds the nearest stored yield rate before the timestamp (or after it, if `after` is set).
+// The search stops at the retention period boundary and at the latest record, since there are no records beyond them
+fn get_nearest_yield_rate(e: &Env, asset: u8, timestamp: u64, after: bool) -> Option<(u64, i128)> {
+    let resolution: u64 = e.get_resolution().into();
+    if after {
+        let (latest_timestamp, _) = e.get_latest_yield_rate(asset)?;
+        let mut next_timestamp = timestamp + resolution;
+        while next_timestamp <= latest_timestamp {
+            if let Some(yield_rate) = e.get_last_yield_rate(asset, next_timestamp) {
+                return Some((next_timestamp, yield_rate));
+            }
+            next_timestamp += resolution;
+        }
+    } else {
+        let oldest_timestamp = (e.ledger().timestamp() * 1000).saturating_sub(e.get_retention_period());
+        let mut prev_timestamp = timestamp;
+        while prev_timestamp >= resolution && prev_timestamp - resolution > oldest_timestamp {
+            prev_timestamp -= resolution;
+            if let Some(yield_rate) = e.get_last_yield_rate(asset, prev_timestamp) {
+                return Some((prev_timestamp, yield_rate));
+            }
+        }
+    }
+    None
+}
+
+fn publish_yield_rate_rejected(e: &Env, asset: u8, yield_rate: i128) {
+    let prev_rate = match e.get_latest_yield_rate(asset) {
+        Some((_, prev_rate)) => prev_rate,
+        None => return,
+    };
+    // The deviation is reported in basis points with the contract decimals for precision
+    let deviation = (yield_rate - prev_rate)
+        .checked_mul(10_000)
+        .zip(10i128.checked_pow(e.get_decimals()))
+        .and_then(|(val, scale)| val.checked_mul(scale))
+        .map(|val| val / prev_rate);
+    e.events().publish(
+        (Symbol::new(e, "yield_rate_rejected"), asset as u32),
+        (prev_rate, yield_rate, deviation),
+    );
 }
 
 fn get_instance_storage(e: &Env) -> Instance {
@@ -358,74 +1181,234 @@ fn get_temporary_storage(e: &Env) -> Temporary {
     e.storage().temporary()
 }
 
-// The yield rate is sent as a 14 decimal place number, such as 110987898736637 (for 1.10987898736637%)
-// To get the price with yield, we need to multiply the fx rate of the fiat by this yield percent,
-// and then divide by 10^14 to get the price with yield.
-fn get_price_with_yield(e: &Env, yield_rate: i128, fx_price: i128, decimals: u32) -> i128 {
-    // Use checked multiplication to prevent overflow
-    let intermediate = match fx_price.checked_mul(yield_rate) {
-        Some(val) => val,
-        None => panic_with_error!(e, Error::IntegerOverflow),
+// The yield rate is sent with the configured yield decimals, such as 110987898736637 (for 1.10987898736637 with 14 decimals)
+// To get the price with yield, we need to multiply the fx rate of the fiat by this yield rate,
+// and then divide by 10^yield_decimals to get the price with yield (rounded down).
+fn get_price_with_yield(yield_rate: i128, fx_price: i128, yield_decimals: u32) -> Result<i128, Error> {
+    let divisor = 10i128.checked_pow(yield_decimals).ok_or(Error::IntegerOverflow)?;
+    mul_div(yield_rate, fx_price, divisor, Rounding::Floor).ok_or(Error::IntegerOverflow)
+}
+
+// yield_rate * fx_price / 10^yield_decimals * 10^decimals / base_price, with the fx price quoted in the fx oracle base
+fn get_price_with_yield_in_base(
+    yield_rate: i128,
+    fx_price: i128,
+    base_price: i128,
+    yield_decimals: u32,
+    decimals: u32,
+) -> Result<i128, Error> {
+    if base_price <= 0 {
+        return Err(Error::InvalidFxPrice);
+    }
+    let (yield_rate, divisor) = if yield_decimals >= decimals {
+        let scale = 10i128.checked_pow(yield_decimals - decimals);
+        (Some(yield_rate), scale.and_then(|scale| base_price.checked_mul(scale)))
+    } else {
+        let scale = 10i128.checked_pow(decimals - yield_decimals);
+        (scale.and_then(|scale| yield_rate.checked_mul(scale)), Some(base_price))
     };
-    
-    // Use checked division to handle edge cases
-    let divisor = match 10i128.checked_pow(decimals) {
-        Some(val) => val,
-        None => panic_with_error!(e, Error::IntegerOverflow),
+    yield_rate
+        .zip(divisor)
+        .and_then(|(yield_rate, divisor)| mul_div(yield_rate, fx_price, divisor, Rounding::Floor))
+        .ok_or(Error::IntegerOverflow)
+}
+
+// Applies the yield to the fx price. If the fx price was converted to the contract base, the yield and the base
+// conversion are applied to the oracle fx and base prices in a single step, so the converted fx price isn't rounded first
+fn get_record_price(e: &Env, yield_rate: i128, fx_price: i128, oracle_prices: Option<(i128, i128)>) -> Result<i128, Error> {
+    let yield_decimals = e.get_yield_decimals();
+    match oracle_prices {
+        Some((oracle_fx_price, base_price)) => {
+            get_price_with_yield_in_base(yield_rate, oracle_fx_price, base_price, yield_decimals, e.get_decimals())
+        }
+        None => get_price_with_yield(yield_rate, fx_price, yield_decimals),
+    }
+}
+
+// Returns the fx price quoted in the fx oracle base and the price of the contract base currency,
+// or None if the contract base is the fx oracle base
+fn get_fx_source_prices(e: &Env, fx: &Symbol, contract_next_timestamp: u64) -> Result<(i128, Option<i128>), Error> {
+    let base_symbol = get_base_symbol(e);
+    if base_symbol.as_ref() == Some(fx) {
+        // The base currency is always 1.0
+        return Ok((10i128.checked_pow(e.get_decimals()).ok_or(Error::IntegerOverflow)?, None));
+    }
+    let fx_price = get_fx_oracle_base_price(e, fx, contract_next_timestamp)?;
+    let base_price = match base_symbol {
+        // The base currency leg goes through the same source, route, mode and max age checks as the fx
+        Some(base_symbol) => Some(get_fx_oracle_base_price(e, &base_symbol, contract_next_timestamp)?),
+        None => None,
     };
-    match intermediate.checked_div(divisor) {
-        Some(val) => val,
-        None => panic_with_error!(e, Error::IntegerOverflow),
+    Ok((fx_price, base_price))
+}
+
+fn get_fx_oracle_base_price(e: &Env, fx: &Symbol, contract_next_timestamp: u64) -> Result<i128, Error> {
+    let fx_index = e.get_fx_index(fx);
+    let source = fx_index.map_or(FxSource::Reflector, |fx_index| e.get_fx_source(fx_index));
+    match source {
+        FxSource::Reflector => {
+            let route = fx_index.map_or(Vec::new(e), |fx_index| e.get_fx_route(fx_index));
+            get_reflector_route_price(e, fx, fx_index, &route, contract_next_timestamp)
+        }
+        FxSource::Peg(rate) => Ok(rate),
+        FxSource::Manual(rate, expiration) => {
+            // Manual rates can't be used after they expire (expiration is in seconds)
+            if e.ledger().timestamp() > expiration {
+                return Err(Error::StaleFxPrice);
+            }
+            Ok(rate)
+        }
     }
 }
 
-fn get_reflector_fx_price(e: &Env, fx: Symbol, contract_next_timestamp: u64) -> i128 {
-    if fx == Symbol::new(e, "USD") {
-        return match 10i128.checked_pow(e.get_decimals()) {
-            Some(val) => val,
-            None => panic_with_error!(e, Error::IntegerOverflow),
-        };
+fn get_base_symbol(e: &Env) -> Option<Symbol> {
+    // The contract base differs from the fx oracle base only for fx symbols quoted by the fx oracle
+    let base_asset = e.get_base_asset();
+    if base_asset == e.get_fx_base() {
+        return None;
     }
-    let reflector_client = get_reflector_oracle(e);
-    let ticker = Asset::Other(fx);
-    
-    // Get the last price from the oracle (single call instead of last_timestamp + price)
-    let price_data = reflector_client.lastprice(&ticker);
-    if price_data.is_none() {
-        panic_with_error!(&e, Error::StaleFxPrice);
+    match base_asset {
+        Asset::Other(base_symbol) => Some(base_symbol),
+        Asset::Stellar(_) => None,
     }
-    
-    let price_data = price_data.unwrap();
-    
-    // Check timestamp drift: oracle timestamp should be within 2 resolutions of contract's next timestamp
-    if contract_next_timestamp > 0 {
-        // Convert oracle timestamp from seconds to milliseconds
-        let oracle_timestamp_ms = match price_data.timestamp.checked_mul(1000) {
-            Some(val) => val,
-            None => panic_with_error!(e, Error::IntegerOverflow),
-        };
-        let resolution_ms = e.get_resolution() as u64; // resolution is in milliseconds
-        let max_drift = 2 * resolution_ms;
-        
-        // Calculate absolute difference
-        let drift = oracle_timestamp_ms.abs_diff(contract_next_timestamp);
-        
-        if drift > max_drift {
-            panic_with_error!(&e, Error::FxOracleTimestampDrift);
+}
+
+fn convert_to_contract_base(e: &Env, fx_price: i128, base_price: i128) -> Result<i128, Error> {
+    // fx_price * 10^decimals / base_price
+    let one = 10i128.checked_pow(e.get_decimals()).ok_or(Error::IntegerOverflow)?;
+    if base_price <= 0 {
+        return Err(Error::InvalidFxPrice);
+    }
+    let fx_price = mul_div(fx_price, one, base_price, Rounding::Floor).ok_or(Error::IntegerOverflow)?;
+    if fx_price <= 0 {
+        return Err(Error::InvalidFxPrice);
+    }
+    Ok(fx_price)
+}
+
+fn get_reflector_fx_price_at(e: &Env, fx: &Symbol, timestamp: u64) -> Option<i128> {
+    let reflector_client = get_reflector_oracle(e).ok()?;
+    match reflector_client.try_price(&Asset::Other(fx.clone()), &(timestamp / 1000)) {
+        Ok(Ok(Some(price_data))) if price_data.price > 0 => rescale_fx_price(e, price_data.price).ok(),
+        _ => None,
+    }
+}
+
+fn get_default_fx_max_age(e: &Env) -> u64 {
+    // Default to 2 fx oracle resolutions, so a single missed fx oracle tick is tolerated
+    2 * e.get_fx_resolution() as u64
+}
+
+fn get_reflector_route_price(
+    e: &Env,
+    fx: &Symbol,
+    fx_index: Option<u8>,
+    route: &Vec<Symbol>,
+    contract_next_timestamp: u64,
+) -> Result<i128, Error> {
+    let reflector_client = get_reflector_oracle(e)?;
+    // Symbols missing from the fx list (such as the contract base) use the default max age and the spot price
+    let (max_drift, mode) = match fx_index {
+        Some(fx_index) => (e.get_fx_max_age(fx_index), e.get_fx_mode(fx_index)), // max age is in milliseconds
+        None => (get_default_fx_max_age(e), FxMode::Spot),
+    };
+    let scale = 10i128.checked_pow(e.get_decimals()).ok_or(Error::IntegerOverflow)?;
+
+    // Follow the fx route (if configured), multiplying the cross prices of each leg
+    let mut fx_price: Option<i128> = None;
+    let mut base = fx.clone();
+    for quote in route.iter().map(Some).chain([None]) {
+        let (leg_price, leg_timestamp) = get_reflector_fx_leg(e, &reflector_client, &base, quote.as_ref(), &mode)?;
+
+        // Check timestamp drift: oracle timestamp should be within the fx max age of contract's next timestamp
+        if contract_next_timestamp > 0 && leg_timestamp.abs_diff(contract_next_timestamp) > max_drift {
+            return Err(Error::FxOracleTimestampDrift);
+        }
+
+        fx_price = Some(match fx_price {
+            None => leg_price,
+            Some(fx_price) => mul_div(fx_price, leg_price, scale, Rounding::Floor).ok_or(Error::IntegerOverflow)?,
+        });
+        if let Some(quote) = quote {
+            base = quote;
         }
     }
-    
+    let fx_price = fx_price.ok_or(Error::StaleFxPrice)?;
+    if fx_price <= 0 {
+        // The fx price is too small to be represented with the contract decimals
+        return Err(Error::InvalidFxPrice);
+    }
+    Ok(fx_price)
+}
+
+fn get_reflector_fx_leg(
+    e: &Env,
+    reflector_client: &PriceOracleContractClient,
+    base: &Symbol,
+    quote: Option<&Symbol>,
+    mode: &FxMode,
+) -> Result<(i128, u64), Error> {
+    let ticker = Asset::Other(base.clone());
+    let quote = quote.map(|quote| Asset::Other(quote.clone()));
+
+    // Get the last price from the oracle (single call instead of last_timestamp + price),
+    // using the cross price for intermediate legs of the route
+    let price_data = match &quote {
+        None => reflector_client.try_lastprice(&ticker),
+        Some(quote) => reflector_client.try_x_last_price(&ticker, quote),
+    };
+    let price_data = match price_data {
+        Ok(Ok(price_data)) => price_data,
+        _ => return Err(Error::FxOracleUnavailable),
+    };
+    let price_data = price_data.ok_or(Error::StaleFxPrice)?;
+    // Convert oracle timestamp from seconds to milliseconds
+    let timestamp = price_data.timestamp.checked_mul(1000).ok_or(Error::IntegerOverflow)?;
+
+    // Smooth the fx price with the oracle TWAP if configured, the last price still guards against a stale feed
+    let fx_price = match mode {
+        FxMode::Spot => price_data.price,
+        FxMode::Twap(records) => {
+            let twap = match &quote {
+                None => reflector_client.try_twap(&ticker, records),
+                Some(quote) => reflector_client.try_x_twap(&ticker, quote, records),
+            };
+            match twap {
+                Ok(Ok(twap)) => twap.ok_or(Error::StaleFxPrice)?,
+                _ => return Err(Error::FxOracleUnavailable),
+            }
+        }
+    };
+
     // Validate the price
-    let fx_price = price_data.price;
     if fx_price <= 0 {
-        panic_with_error!(&e, Error::InvalidFxPrice);
+        return Err(Error::InvalidFxPrice);
+    }
+    Ok((rescale_fx_price(e, fx_price)?, timestamp))
+}
+
+fn rescale_fx_price(e: &Env, fx_price: i128) -> Result<i128, Error> {
+    // Convert the fx price from the fx oracle decimals to the contract decimals
+    let decimals = e.get_decimals();
+    let fx_decimals = e.get_fx_decimals();
+    if fx_decimals == decimals {
+        return Ok(fx_price);
+    }
+    if fx_decimals < decimals {
+        let multiplier = 10i128.checked_pow(decimals - fx_decimals).ok_or(Error::IntegerOverflow)?;
+        return fx_price.checked_mul(multiplier).ok_or(Error::IntegerOverflow);
+    }
+    let divisor = 10i128.checked_pow(fx_decimals - decimals).ok_or(Error::IntegerOverflow)?;
+    let fx_price = fx_price / divisor;
+    if fx_price <= 0 {
+        // The fx price is too small to be represented with the contract decimals
+        return Err(Error::InvalidFxPrice);
     }
-    fx_price
+    Ok(fx_price)
 }
 
-fn get_reflector_oracle(e: &Env) -> PriceOracleContractClient {
+fn get_reflector_oracle(e: &Env) -> Result<PriceOracleContractClient<'_>, Error> {
     // Get the FX oracle address from storage (set during config)
-    let oracle_address = e.get_fx_oracle_address()
-        .unwrap_or_else(|| panic_with_error!(e, Error::FxOracleUnavailable));
-    PriceOracleContractClient::new(&e, &oracle_address)
+    let oracle_address = e.get_fx_oracle_address().ok_or(Error::FxOracleUnavailable)?;
+    Ok(PriceOracleContractClient::new(&e, &oracle_address))
 }
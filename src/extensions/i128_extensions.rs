@@ -1,42 +1,149 @@
+use crate::types::{error::Error, rounding::Rounding};
+
 pub trait I128Extensions {
     // Divides two i128 numbers, considering decimal places.
     //
     // Arguments:
     // - self: The dividend.
-    // - y: The divisor. Should not be zero; will cause panic if zero.
+    // - y: The divisor.
     // - decimals: Number of decimal places for division.
     //
     // Behavior:
-    // - Rounds up towards zero for negative results.
+    // - Rounds down the exact result of self * 10^decimals / y.
+    // - Returns zero for a zero dividend (self).
     //
-    // Panic:
-    // - If dividend (self) or divisor (y) is zero.
+    // Errors:
+    // - DivisionByZero if the divisor (y) is zero.
+    // - NegativePrice if the dividend (self) or divisor (y) is negative.
+    // - IntegerOverflow if the result doesn't fit into i128.
     //
     // Returns:
     // - Division result with specified rounding behavior.
-    fn fixed_div_floor(self, y: i128, decimals: u32) -> i128;
+    fn fixed_div_floor(self, y: i128, decimals: u32) -> Result<i128, Error>;
+
+    // Rescales a fixed-point i128 number to a different number of decimal places.
+    //
+    // Arguments:
+    // - self: The value to rescale.
+    // - from_decimals: Number of decimal places of the value.
+    // - to_decimals: Number of decimal places of the result.
+    // - rounding: Rounding mode applied when precision is dropped.
+    //
+    // Returns:
+    // - Rescaled value or None if the result overflows.
+    fn rescale(self, from_decimals: u32, to_decimals: u32, rounding: Rounding) -> Option<i128>;
 }
 
 impl I128Extensions for i128 {
-    fn fixed_div_floor(self, y: i128, decimals: u32) -> i128 {
+    fn fixed_div_floor(self, y: i128, decimals: u32) -> Result<i128, Error> {
         div_floor(self, y, decimals)
     }
+
+    fn rescale(self, from_decimals: u32, to_decimals: u32, rounding: Rounding) -> Option<i128> {
+        if to_decimals >= from_decimals {
+            return 10_i128
+                .checked_pow(to_decimals - from_decimals)
+                .and_then(|factor| self.checked_mul(factor));
+        }
+        let divisor = 10_i128.checked_pow(from_decimals - to_decimals)?;
+        mul_div(self, 1, divisor, rounding)
+    }
 }
 
-fn div_floor(dividend: i128, divisor: i128, decimals: u32) -> i128 {
-    if dividend <= 0 || divisor <= 0 {
-        panic!("invalid division arguments")
+// Computes a * b / denominator with a 256-bit intermediate product, so only the final result has to fit into i128.
+//
+// Arguments:
+// - a: The multiplicand.
+// - b: The multiplier.
+// - denominator: The divisor.
+// - rounding: Rounding mode applied to the exact quotient.
+//
+// Returns:
+// - The rounded quotient or None if the denominator is zero or the result doesn't fit into i128.
+pub fn mul_div(a: i128, b: i128, denominator: i128, rounding: Rounding) -> Option<i128> {
+    if denominator == 0 {
+        return None;
+    }
+    let negative = (a < 0) ^ (b < 0) ^ (denominator < 0);
+    let divisor = denominator.unsigned_abs();
+    let (high, low) = mul_u256(a.unsigned_abs(), b.unsigned_abs());
+    let (quotient, remainder) = div_u256(high, low, divisor)?;
+
+    // Round the magnitude, keeping in mind that floor and ceil are mirrored for negative results
+    let round_up = if remainder == 0 {
+        false
+    } else {
+        let rest = divisor - remainder; //distance to the next multiple, compared without doubling to avoid overflow
+        match rounding {
+            Rounding::Floor => negative,
+            Rounding::Ceil => !negative,
+            Rounding::HalfUp => remainder >= rest,
+            Rounding::HalfEven => remainder > rest || (remainder == rest && quotient % 2 != 0),
+        }
+    };
+    let magnitude = if round_up { quotient.checked_add(1)? } else { quotient };
+
+    if negative {
+        if magnitude == i128::MIN.unsigned_abs() {
+            return Some(i128::MIN);
+        }
+        i128::try_from(magnitude).ok().map(|val| -val)
+    } else {
+        i128::try_from(magnitude).ok()
     }
-    let ashift = core::cmp::min(38 - dividend.ilog10(), decimals);
-    let bshift = core::cmp::max(decimals - ashift, 0);
+}
+
+// Multiplies two u128 numbers into a 256-bit (high, low) pair.
+fn mul_u256(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let high_high = a_high * b_high;
+
+    //the middle sum can't overflow: it's at most 3 * (2^64 - 1)
+    let middle = (low_low >> 64) + (low_high & MASK) + (high_low & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    (high, low)
+}
+
+// Divides a 256-bit (high, low) pair by a u128 number, returning the quotient and remainder.
+// Returns None if the quotient doesn't fit into u128.
+fn div_u256(high: u128, low: u128, divisor: u128) -> Option<(u128, u128)> {
+    if high >= divisor {
+        return None;
+    }
+    if high == 0 {
+        return Some((low / divisor, low % divisor));
+    }
+    //binary long division, the remainder is always below the divisor between the steps
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
 
-    let mut vdividend = dividend;
-    let mut vdivisor = divisor;
-    if ashift > 0 {
-        vdividend *= 10_i128.pow(ashift);
+fn div_floor(dividend: i128, divisor: i128, decimals: u32) -> Result<i128, Error> {
+    if divisor == 0 {
+        return Err(Error::DivisionByZero);
     }
-    if bshift > 0 {
-        vdivisor /= 10_i128.pow(bshift);
+    if dividend < 0 || divisor < 0 {
+        return Err(Error::NegativePrice);
     }
-    vdividend / vdivisor
+    10_i128
+        .checked_pow(decimals)
+        .and_then(|scale| mul_div(dividend, scale, divisor, Rounding::Floor))
+        .ok_or(Error::IntegerOverflow)
 }
@@ -1,15 +1,21 @@
 #![no_std]
 
+mod constants;
 mod extensions;
 mod test;
 mod types;
 
-use extensions::i128_extensions::I128Extensions;
-use extensions::{env_extensions::EnvExtensions, u64_extensions::U64Extensions};
-use soroban_sdk::{Address, BytesN, Env, Symbol, Vec, contract, contractimpl, panic_with_error};
+use constants::YEAR_MS;
+use extensions::i128_extensions::{I128Extensions, mul_div};
+use extensions::{env_extensions::{EnvExtensions, ValidatedPrice}, u64_extensions::U64Extensions};
+use soroban_sdk::{Address, BytesN, Env, String, Symbol, Vec, contract, contractimpl, panic_with_error};
 use types::asset::Asset;
 use types::error::Error;
-use types::{config_data::ConfigData, price_data::PriceData};
+use types::{config_data::ConfigData, fx_guard::FxGuard, fx_mode::FxMode, fx_source::FxSource, price_band::PriceBand, price_data::PriceData};
+use types::projected_price_data::ProjectedPriceData;
+use types::rounding::Rounding;
+use types::update_result::UpdateResult;
+use types::validation_policy::ValidationPolicy;
 
 #[contract]
 pub struct PriceOracleContract;
@@ -34,6 +40,15 @@ impl PriceOracleContract {
         e.get_decimals()
     }
 
+    // Returns the number of decimal places used to represent yield rates published to the oracle.
+    //
+    // # Returns
+    //
+    // Number of decimals places in yield rates
+    pub fn yield_decimals(e: Env) -> u32 {
+        e.get_yield_decimals()
+    }
+
     // Returns the default tick period timeframe (in seconds).
     //
     // # Returns
@@ -93,6 +108,28 @@ impl PriceOracleContract {
         get_price_data(&e, asset, normalized_timestamp)
     }
 
+    // Returns price in base asset at specific timestamp, reporting why the price is not available.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `timestamp` - Timestamp in seconds
+    //
+    // # Returns
+    //
+    // Price record for the given asset at the given timestamp, AssetNotSupported error if the asset is not supported,
+    // InvalidTimestamp error if the timestamp overflows when converted to milliseconds or PriceNotFound error if the
+    // record was not found
+    pub fn price_checked(e: Env, asset: Asset, timestamp: u64) -> Result<PriceData, Error> {
+        let asset = e.get_asset_index(&asset).ok_or(Error::AssetNotSupported)?;
+        let resolution = e.get_resolution();
+        let normalized_timestamp = timestamp //convert to milliseconds and normalize
+            .checked_mul(1000)
+            .ok_or(Error::InvalidTimestamp)?
+            .get_normalized_timestamp(resolution.into());
+        get_price_data_by_index(&e, asset, normalized_timestamp).ok_or(Error::PriceNotFound)
+    }
+
     // Returns the most recent price for an asset.
     //
     // # Arguments
@@ -112,6 +149,73 @@ impl PriceOracleContract {
         get_price_data(&e, asset, timestamp)
     }
 
+    // Returns the most recent price for an asset, reporting why the price is not available.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    //
+    // # Returns
+    //
+    // The most recent price for the given asset, AssetNotSupported error if the asset is not supported,
+    // PriceNotFound error if there are no prices yet or StalePrice error if the most recent prices are too old
+    pub fn lastprice_checked(e: Env, asset: Asset) -> Result<PriceData, Error> {
+        let asset = e.get_asset_index(&asset).ok_or(Error::AssetNotSupported)?;
+        let timestamp = obtain_record_timestamp_checked(&e)?;
+        get_price_data_by_index(&e, asset, timestamp).ok_or(Error::PriceNotFound)
+    }
+
+    // Returns the most recent price for an asset rescaled to the requested number of decimals.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `decimals` - Number of decimal places in the returned price
+    // * `rounding` - Rounding mode applied when precision is dropped
+    //
+    // # Returns
+    //
+    // The most recent rescaled price for the given asset or None if the asset is not supported
+    //
+    // # Panics
+    //
+    // Panics if the rescaled price overflows
+    pub fn lastprice_scaled(e: Env, asset: Asset, decimals: u32, rounding: Rounding) -> Option<PriceData> {
+        let price_data = Self::lastprice(e.clone(), asset)?;
+        Some(rescale_price_data(&e, price_data, decimals, rounding))
+    }
+
+    // Returns the most recent prices for multiple assets in a single call.
+    //
+    // # Arguments
+    //
+    // * `assets` - Assets to quote
+    //
+    // # Returns
+    //
+    // The most recent price for each of the given assets (in the same order), or None for the assets that are not supported
+    pub fn lastprices(e: Env, assets: Vec<Asset>) -> Vec<Option<PriceData>> {
+        //get the last timestamp once for all assets
+        let timestamp = e.obtain_record_timestamp();
+        get_prices_data(&e, assets, timestamp)
+    }
+
+    // Returns prices for multiple assets at specific timestamp in a single call.
+    //
+    // # Arguments
+    //
+    // * `assets` - Assets to quote
+    // * `timestamp` - Timestamp in seconds
+    //
+    // # Returns
+    //
+    // Price records for each of the given assets (in the same order) at the given timestamp, or None for the assets without a record
+    pub fn prices_at(e: Env, assets: Vec<Asset>, timestamp: u64) -> Vec<Option<PriceData>> {
+        let normalized_timestamp = //convert to milliseconds and normalize
+            (timestamp * 1000).get_normalized_timestamp(e.get_resolution().into());
+        get_prices_data(&e, assets, normalized_timestamp)
+    }
+
     // Returns last N price records for the given asset.
     //
     // # Arguments
@@ -134,6 +238,37 @@ impl PriceOracleContract {
         )
     }
 
+    // Returns last N price records for the given asset rescaled to the requested number of decimals.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `records` - Number of records to return
+    // * `decimals` - Number of decimal places in the returned prices
+    // * `rounding` - Rounding mode applied when precision is dropped
+    //
+    // # Returns
+    //
+    // Rescaled prices for the given asset or None if the asset is not supported
+    //
+    // # Panics
+    //
+    // Panics if a rescaled price overflows
+    pub fn prices_scaled(
+        e: Env,
+        asset: Asset,
+        records: u32,
+        decimals: u32,
+        rounding: Rounding,
+    ) -> Option<Vec<PriceData>> {
+        let prices = Self::prices(e.clone(), asset, records)?;
+        let mut scaled = Vec::new(&e);
+        for price_data in prices.iter() {
+            scaled.push_back(rescale_price_data(&e, price_data, decimals, rounding));
+        }
+        Some(scaled)
+    }
+
     // Returns the most recent cross price record for the pair of assets.
     //
     // # Arguments
@@ -153,6 +288,25 @@ impl PriceOracleContract {
         get_x_price(&e, base_asset, quote_asset, timestamp, decimals)
     }
 
+    // Returns the most recent cross price record for the pair of assets, reporting why the price is not available.
+    //
+    // # Arguments
+    //
+    // * `base_asset` - Base asset
+    // * `quote_asset` - Quote asset
+    //
+    // # Returns
+    //
+    // The most recent cross price (base_asset_price/quote_asset_price) for the given assets, AssetNotSupported error
+    // if any of the assets is not supported, PriceNotFound or StalePrice error if there are no recent records for the
+    // assets, or the arithmetic error if the cross price can't be calculated
+    pub fn x_last_price_checked(e: Env, base_asset: Asset, quote_asset: Asset) -> Result<PriceData, Error> {
+        let asset_pair_indexes =
+            get_asset_pair_indexes(&e, base_asset, quote_asset).ok_or(Error::AssetNotSupported)?;
+        let timestamp = obtain_record_timestamp_checked(&e)?;
+        get_x_price_by_indexes_checked(&e, asset_pair_indexes, timestamp, e.get_decimals())
+    }
+
     // Returns the cross price for the pair of assets at specific timestamp.
     //
     // # Arguments
@@ -228,6 +382,43 @@ impl PriceOracleContract {
         )
     }
 
+    // Returns the time-weighted average price for the given asset over N recent records, reporting why the TWAP is not available.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `records` - Number of records to process
+    //
+    // # Returns
+    //
+    // TWAP for the given asset over N recent records, AssetNotSupported error if the asset is not supported,
+    // PriceNotFound error if there are less than N records or StalePrice error if the most recent records are too old
+    pub fn twap_checked(e: Env, asset: Asset, records: u32) -> Result<i128, Error> {
+        let asset = e.get_asset_index(&asset).ok_or(Error::AssetNotSupported)?;
+        get_twap_checked(&e, |timestamp| get_price_data_by_index(&e, asset, timestamp), records)
+    }
+
+    // Returns the time-weighted average price for the given asset over N recent records rescaled to the requested number of decimals.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `records` - Number of records to process
+    // * `decimals` - Number of decimal places in the returned TWAP
+    // * `rounding` - Rounding mode applied when precision is dropped
+    //
+    // # Returns
+    //
+    // Rescaled TWAP for the given asset over N recent records or None if the asset is not supported
+    //
+    // # Panics
+    //
+    // Panics if the rescaled TWAP overflows
+    pub fn twap_scaled(e: Env, asset: Asset, records: u32, decimals: u32, rounding: Rounding) -> Option<i128> {
+        let twap = Self::twap(e.clone(), asset, records)?;
+        Some(rescale_price(&e, twap, decimals, rounding))
+    }
+
     // Returns the time-weighted average cross price for the given asset pair over N recent records.
     //
     // # Arguments
@@ -254,6 +445,204 @@ impl PriceOracleContract {
         )
     }
 
+    // Returns the most recent price for an asset quoted in the given FX currency.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `fx` - FX currency symbol to quote the price in
+    //
+    // # Returns
+    //
+    // The most recent price (asset_price/fx_price) or None if the asset or FX currency is not supported
+    pub fn lastprice_in(e: Env, asset: Asset, fx: Symbol) -> Option<PriceData> {
+        let timestamp = e.obtain_record_timestamp();
+        if timestamp == 0 {
+            return None;
+        }
+        get_price_data_in(&e, asset, fx, timestamp)
+    }
+
+    // Returns price for an asset quoted in the given FX currency at specific timestamp.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `fx` - FX currency symbol to quote the price in
+    // * `timestamp` - Timestamp in seconds
+    //
+    // # Returns
+    //
+    // Price (asset_price/fx_price) at the given timestamp or None if there were no records found for the asset or FX currency
+    pub fn price_in(e: Env, asset: Asset, fx: Symbol, timestamp: u64) -> Option<PriceData> {
+        let normalized_timestamp = //convert to milliseconds and normalize
+            (timestamp * 1000).get_normalized_timestamp(e.get_resolution().into());
+        get_price_data_in(&e, asset, fx, normalized_timestamp)
+    }
+
+    // Returns the most recent yield rate (accrual index) for an asset, independent of FX.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    //
+    // # Returns
+    //
+    // The most recent yield rate for the given asset in the `price` field or None if the asset is not supported
+    pub fn last_yield_rate(e: Env, asset: Asset) -> Option<PriceData> {
+        //get the last timestamp
+        let timestamp = e.obtain_record_timestamp();
+        if timestamp == 0 {
+            return None;
+        }
+        let asset_index = e.get_asset_index(&asset)?;
+        get_yield_rate_data_by_index(&e, asset_index, timestamp)
+    }
+
+    // Returns the yield rate (accrual index) for an asset at specific timestamp.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `timestamp` - Timestamp in seconds
+    //
+    // # Returns
+    //
+    // Yield rate record for the given asset at the given timestamp or None if the record was not found
+    pub fn yield_rate(e: Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
+        let normalized_timestamp = //convert to milliseconds and normalize
+            (timestamp * 1000).get_normalized_timestamp(e.get_resolution().into());
+        let asset_index = e.get_asset_index(&asset)?;
+        get_yield_rate_data_by_index(&e, asset_index, normalized_timestamp)
+    }
+
+    // Returns last N yield rate records for the given asset.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `records` - Number of records to return
+    //
+    // # Returns
+    //
+    // Yield rates for the given asset or None if the asset is not supported
+    pub fn yield_rates(e: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>> {
+        let asset_index = e.get_asset_index(&asset)?; //get the asset index to avoid multiple calls
+        prices(
+            &e,
+            |timestamp| get_yield_rate_data_by_index(&e, asset_index, timestamp),
+            records,
+        )
+    }
+
+    // Returns the time-weighted average yield rate for the given asset over N recent records.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `records` - Number of records to process
+    //
+    // # Returns
+    //
+    // Time-weighted average yield rate for the given asset over N recent records or None if the asset is not supported
+    pub fn yield_twap(e: Env, asset: Asset, records: u32) -> Option<i128> {
+        let asset_index = e.get_asset_index(&asset)?; //get the asset index to avoid multiple calls
+        get_twap(
+            &e,
+            |timestamp| get_yield_rate_data_by_index(&e, asset_index, timestamp),
+            records,
+        )
+    }
+
+    // Returns the annualized rate of an asset derived from the yield rate history.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `lookback` - Period (in seconds) to derive the growth of the yield rate from
+    //
+    // # Returns
+    //
+    // Simple (non-compounded) annualized rate in basis points with `apr_decimals` decimal places or None if there were no yield rate records found for the period
+    pub fn apr(e: Env, asset: Asset, lookback: u64) -> Option<i128> {
+        let timestamp = e.obtain_record_timestamp();
+        if timestamp == 0 {
+            return None;
+        }
+        let asset_index = e.get_asset_index(&asset)?;
+        get_apr(&e, asset_index, timestamp, lookback.checked_mul(1000)?) //convert to milliseconds
+    }
+
+    // Returns the annualized rates of an asset for every record within the given window.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `window` - Period (in seconds) to return the records for, each rate is derived from the growth over a single resolution period
+    //
+    // # Returns
+    //
+    // Annualized rates (in basis points with `apr_decimals` decimal places) in the `price` field or None if the asset is not supported
+    pub fn apr_history(e: Env, asset: Asset, window: u64) -> Option<Vec<PriceData>> {
+        let asset_index = e.get_asset_index(&asset)?; //get the asset index to avoid multiple calls
+        let resolution = e.get_resolution() as u64;
+        let records = window.checked_mul(1000)? / resolution; //convert to milliseconds
+        prices(
+            &e,
+            |timestamp| {
+                let apr = get_apr(&e, asset_index, timestamp, resolution)?;
+                Some(get_normalized_price_data(apr, timestamp))
+            },
+            records.min(u32::MAX as u64) as u32,
+        )
+    }
+
+    // Returns the number of decimal places used to represent annualized rates (in basis points).
+    //
+    // # Returns
+    //
+    // Number of decimals places in annualized rates
+    pub fn apr_decimals(e: Env) -> u32 {
+        e.get_apr_decimals()
+    }
+
+    // Returns the price for an asset projected to the given timestamp from the most recent record.
+    //
+    // The price is extrapolated using the configured APR of the asset, or the growth between the last two
+    // yield rate records otherwise. Projections are limited to two resolution periods after the last record.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    // * `timestamp` - Timestamp in seconds to project the price to
+    //
+    // # Returns
+    //
+    // Projected price record for the given asset or None if the asset is not supported or the timestamp is too far from the last record
+    pub fn projected_price(e: Env, asset: Asset, timestamp: u64) -> Option<ProjectedPriceData> {
+        //get the last timestamp
+        let last_timestamp = e.obtain_record_timestamp();
+        if last_timestamp == 0 {
+            return None;
+        }
+        let asset_index = e.get_asset_index(&asset)?;
+        get_projected_price_data(&e, asset_index, last_timestamp, timestamp * 1000) //convert to milliseconds
+    }
+
+    // Returns the configured APR used to project the price of an asset.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    //
+    // # Returns
+    //
+    // APR in basis points or None if the APR is not configured for the asset
+    pub fn asset_apr(e: Env, asset: Asset) -> Option<u32> {
+        let asset_index = e.get_asset_index(&asset)?;
+        e.get_asset_apr(asset_index)
+    }
+
     // Returns current protocol version of the contract.
     //
     // # Returns
@@ -297,38 +686,423 @@ impl PriceOracleContract {
         e.set_admin(&config.admin);
         e.set_base_asset(&config.base_asset);
         e.set_decimals(config.decimals);
+        e.set_yield_decimals(config.yield_decimals);
         e.set_resolution(config.resolution);
         e.set_retention_period(config.period);
-        e.set_fx_oracle_address(&config.fx_oracle_address);
-        e.set_max_yield_deviation(config.max_yield_deviation_percent);
+        set_fx_oracle(&e, &config.fx_oracle_address);
+        e.set_max_yield_increase(config.max_yield_increase_bps);
+        e.set_max_yield_decrease(config.max_yield_decrease_bps);
     }
 
-    // Adds given assets to the contract quoted assets list. Can be invoked only by the admin account.
+    // Sets the FX oracle address. Can be invoked only by the admin account.
     //
     // # Arguments
     //
-    // * `admin` - Admin account address
-    // * `assets` - Assets to add
-    // * `version` - Configuration protocol version
+    // * `fx_oracle_address` - FX oracle contract address
     //
     // # Panics
     //
-    // Panics if the caller doesn't match admin address, or if the assets are already added
-    pub fn add_assets(e: Env, assets: Vec<Asset>, fxs: Vec<Symbol>) {
+    // Panics if the caller doesn't match admin address, if the FX oracle cannot be accessed, or if it neither uses
+    // nor quotes the contract's base asset
+    pub fn set_fx_oracle_address(e: Env, fx_oracle_address: Address) {
         e.panic_if_not_admin();
-        if assets.len() != fxs.len() {
-            panic_with_error!(&e, Error::FxArrayLengthMismatch);
-        }
-        Self::__add_assets(&e, assets);
-        Self::__add_fxs(&e, fxs);
+        set_fx_oracle(&e, &fx_oracle_address);
     }
 
-    // Sets history retention period for the prices. Can be invoked only by the admin account.
+    // Returns the FX oracle address.
     //
-    // # Arguments
+    // # Returns
     //
-    // * `admin` - Admin account address
-    // * `period` - History retention period (in seconds)
+    // FX oracle contract address
+    pub fn fx_oracle_address(e: Env) -> Option<Address> {
+        e.get_fx_oracle_address()
+    }
+
+    // Returns the number of decimal places used by the FX oracle prices.
+    //
+    // # Returns
+    //
+    // Number of decimals places in FX oracle prices
+    pub fn fx_decimals(e: Env) -> u32 {
+        e.get_fx_decimals()
+    }
+
+    // Sets the maximum age of FX oracle prices for an fx symbol. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to configure
+    // * `max_age` - Maximum FX price age (in seconds)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the fx is not supported, or if the max age overflows
+    // when converted to milliseconds
+    pub fn set_fx_max_age(e: Env, fx: Symbol, max_age: u64) {
+        e.panic_if_not_admin();
+        let fx_index = e
+            .get_fx_index(&fx)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::FxMissing));
+        let max_age = max_age //convert to milliseconds
+            .checked_mul(1000)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::InvalidTimestamp));
+        e.set_fx_max_age(fx_index, max_age);
+    }
+
+    // Returns the maximum age of FX oracle prices for an fx symbol.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    //
+    // # Returns
+    //
+    // Maximum FX price age (in seconds, 2 FX oracle resolutions if not configured) or None if the fx is not supported
+    pub fn fx_max_age(e: Env, fx: Symbol) -> Option<u64> {
+        let fx_index = e.get_fx_index(&fx)?;
+        Some(e.get_fx_max_age(fx_index) / 1000) //convert to seconds
+    }
+
+    // Sets the source of the fx price for an fx symbol. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to configure
+    // * `mode` - Fx price source (spot or TWAP over a number of records)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the fx is not supported, or if the mode is invalid
+    // (TWAP over no records or over more records than the fx oracle retains)
+    pub fn set_fx_mode(e: Env, fx: Symbol, mode: FxMode) {
+        e.panic_if_not_admin();
+        let fx_index = e
+            .get_fx_index(&fx)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::FxMissing));
+        if let FxMode::Twap(records) = mode {
+            if records == 0 || records > get_fx_oracle_max_records(&e) {
+                panic_with_error!(&e, Error::InvalidFxMode);
+            }
+        }
+        e.set_fx_mode(fx_index, &mode);
+    }
+
+    // Returns the source of the fx price for an fx symbol.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    //
+    // # Returns
+    //
+    // Fx price source (spot if not configured) or None if the fx is not supported
+    pub fn fx_mode(e: Env, fx: Symbol) -> Option<FxMode> {
+        let fx_index = e.get_fx_index(&fx)?;
+        Some(e.get_fx_mode(fx_index))
+    }
+
+    // Sets the source of the fx rate for an fx symbol. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to configure
+    // * `source` - Fx rate source (FX oracle feed, fixed peg or manual rate with expiration)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the fx is not supported, or if the source is invalid
+    pub fn set_fx_source(e: Env, fx: Symbol, source: FxSource) {
+        e.panic_if_not_admin();
+        let fx_index = e
+            .get_fx_index(&fx)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::FxMissing));
+        let is_valid = match source {
+            FxSource::Reflector => true,
+            FxSource::Peg(rate) => rate > 0,
+            FxSource::Manual(rate, expiration) => {
+                rate > 0 && expiration.checked_mul(1000).is_some_and(|expiration| expiration > now(&e))
+            }
+        };
+        if !is_valid {
+            panic_with_error!(&e, Error::InvalidFxSource);
+        }
+        e.set_fx_source(fx_index, &source);
+    }
+
+    // Returns the source of the fx rate for an fx symbol.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    //
+    // # Returns
+    //
+    // Fx rate source (a peg for the FX oracle base, the FX oracle feed otherwise, if not configured) or None if the fx
+    // is not supported
+    pub fn fx_source(e: Env, fx: Symbol) -> Option<FxSource> {
+        let fx_index = e.get_fx_index(&fx)?;
+        Some(e.get_fx_source(fx_index))
+    }
+
+    // Sets the route used to derive the fx price when the FX oracle doesn't quote the fx symbol directly.
+    // Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to configure
+    // * `route` - Intermediate fx symbols, e.g. [EUR] for BRL -> EUR -> base (empty to quote the fx symbol directly)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the fx is not supported, or if the route is invalid
+    pub fn set_fx_route(e: Env, fx: Symbol, route: Vec<Symbol>) {
+        e.panic_if_not_admin();
+        let fx_index = e
+            .get_fx_index(&fx)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::FxMissing));
+        if let Err(error) = e.set_fx_route(fx_index, &route) {
+            panic_with_error!(&e, error);
+        }
+    }
+
+    // Returns the route used to derive the fx price for an fx symbol.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    //
+    // # Returns
+    //
+    // Intermediate fx symbols (empty if the fx symbol is quoted directly) or None if the fx is not supported
+    pub fn fx_route(e: Env, fx: Symbol) -> Option<Vec<Symbol>> {
+        let fx_index = e.get_fx_index(&fx)?;
+        Some(e.get_fx_route(fx_index))
+    }
+
+    // Returns the age of the most recent FX oracle price for an fx symbol.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    //
+    // # Returns
+    //
+    // FX price age (in seconds, the oldest leg for routed fx symbols, 0 for pegged and manual rates) or None if the fx
+    // is not supported, the FX oracle has no price, or the manual rate has expired
+    pub fn fx_age(e: Env, fx: Symbol) -> Option<u64> {
+        let fx_index = e.get_fx_index(&fx)?;
+        match e.get_fx_source(fx_index) {
+            FxSource::Reflector => {}
+            FxSource::Peg(_) => return Some(0),
+            FxSource::Manual(_, expiration) => {
+                //manual rates are current until they expire
+                return match expiration.checked_mul(1000) {
+                    Some(expiration) if now(&e) <= expiration => Some(0),
+                    _ => None,
+                };
+            }
+        }
+        let fx_timestamp = e.obtain_fx_timestamp(&fx)?;
+        Some(now(&e).saturating_sub(fx_timestamp) / 1000) //convert to seconds
+    }
+
+    // Adds given assets to the contract quoted assets list. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `admin` - Admin account address
+    // * `assets` - Assets to add
+    // * `version` - Configuration protocol version
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, or if the assets are already added
+    pub fn add_assets(e: Env, assets: Vec<Asset>, fxs: Vec<Symbol>) {
+        e.panic_if_not_admin();
+        if assets.len() != fxs.len() {
+            panic_with_error!(&e, Error::FxArrayLengthMismatch);
+        }
+        Self::__add_assets(&e, assets);
+        Self::__add_fxs(&e, fxs);
+    }
+
+    // Sets the maximum allowed yield rate change between records. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `max_increase_bps` - Maximum allowed yield rate increase in basis points
+    // * `max_decrease_bps` - Maximum allowed yield rate decrease in basis points
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address
+    pub fn set_yield_limits(e: Env, max_increase_bps: u32, max_decrease_bps: u32) {
+        e.panic_if_not_admin();
+        e.set_max_yield_increase(max_increase_bps);
+        e.set_max_yield_decrease(max_decrease_bps);
+    }
+
+    // Returns the maximum allowed yield rate change between records.
+    //
+    // # Returns
+    //
+    // Maximum allowed yield rate increase and decrease in basis points
+    pub fn yield_limits(e: Env) -> (u32, u32) {
+        (e.get_max_yield_increase(), e.get_max_yield_decrease())
+    }
+
+    // Sets the maximum allowed yield rate growth for assets without a validation policy. Can be invoked only by the
+    // admin account.
+    //
+    // # Arguments
+    //
+    // * `max_apr_bps` - Maximum allowed yield rate growth as an APR in basis points, scaled by the time elapsed
+    //   since the previous record (0 to disable)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address
+    pub fn set_max_apr(e: Env, max_apr_bps: u32) {
+        e.panic_if_not_admin();
+        e.set_max_apr(max_apr_bps);
+    }
+
+    // Returns the maximum allowed yield rate growth for assets without a validation policy.
+    //
+    // # Returns
+    //
+    // Maximum allowed yield rate growth as an APR in basis points (100% if not configured)
+    pub fn max_apr(e: Env) -> u32 {
+        e.get_max_apr()
+    }
+
+    // Sets the yield rate validation policy for an asset. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to configure
+    // * `policy` - Yield rate validation policy
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the asset is not supported, or if the policy is invalid
+    pub fn set_asset_policy(e: Env, asset: Asset, policy: ValidationPolicy) {
+        e.panic_if_not_admin();
+        let asset_index = e
+            .get_asset_index(&asset)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::AssetMissing));
+        if policy.min_yield_rate <= 0 || policy.min_yield_rate > policy.max_yield_rate {
+            panic_with_error!(&e, Error::InvalidValidationPolicy);
+        }
+        e.set_asset_policy(asset_index, &policy);
+    }
+
+    // Returns the yield rate validation policy for an asset.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    //
+    // # Returns
+    //
+    // Validation policy of the asset (derived from the global limits if not configured) or None if the asset is not supported
+    pub fn asset_policy(e: Env, asset: Asset) -> Option<ValidationPolicy> {
+        let asset_index = e.get_asset_index(&asset)?;
+        Some(e.get_asset_policy(asset_index))
+    }
+
+    // Sets the final price sanity band for an asset. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to configure
+    // * `band` - Final price limits
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the asset is not supported, or if the band is invalid
+    pub fn set_price_band(e: Env, asset: Asset, band: PriceBand) {
+        e.panic_if_not_admin();
+        let asset_index = e
+            .get_asset_index(&asset)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::AssetMissing));
+        if band.min_price < 0 || band.min_price > band.max_price {
+            panic_with_error!(&e, Error::InvalidPriceBand);
+        }
+        e.set_price_band(asset_index, &band);
+    }
+
+    // Returns the final price sanity band for an asset.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to quote
+    //
+    // # Returns
+    //
+    // Price band of the asset or None if not configured or the asset is not supported
+    pub fn price_band(e: Env, asset: Asset) -> Option<PriceBand> {
+        let asset_index = e.get_asset_index(&asset)?;
+        e.get_price_band(asset_index)
+    }
+
+    // Sets the fx price change limit for an fx symbol. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to configure
+    // * `guard` - Fx price change limit
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, or if the fx is not supported
+    pub fn set_fx_guard(e: Env, fx: Symbol, guard: FxGuard) {
+        e.panic_if_not_admin();
+        let fx_index = e
+            .get_fx_index(&fx)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::FxMissing));
+        e.set_fx_guard(fx_index, &guard);
+    }
+
+    // Returns the fx price change limit for an fx symbol.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    //
+    // # Returns
+    //
+    // Fx price change limit or None if not configured or the fx is not supported
+    pub fn fx_guard(e: Env, fx: Symbol) -> Option<FxGuard> {
+        let fx_index = e.get_fx_index(&fx)?;
+        e.get_fx_guard(fx_index)
+    }
+
+    // Returns the fx price reported by the fx oracle when it was rejected by the fx guard and replaced with the previous
+    // fx price at the given timestamp.
+    //
+    // # Arguments
+    //
+    // * `fx` - Fx symbol to quote
+    // * `timestamp` - Tick timestamp
+    //
+    // # Returns
+    //
+    // Fx price reported by the oracle or None if the fx price was not rejected or the fx is not supported
+    pub fn rejected_fx_price(e: Env, fx: Symbol, timestamp: u64) -> Option<PriceData> {
+        let normalized_timestamp = //convert to milliseconds and normalize
+            (timestamp * 1000).get_normalized_timestamp(e.get_resolution().into());
+        let fx_index = e.get_fx_index(&fx)?;
+        let fx_price = e.get_rejected_fx_price(fx_index, normalized_timestamp)?;
+        Some(get_normalized_price_data(fx_price, normalized_timestamp))
+    }
+
+    // Sets history retention period for the prices. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `admin` - Admin account address
+    // * `period` - History retention period (in seconds)
     // * `version` - Configuration protocol version
     //
     // # Panics
@@ -341,59 +1115,260 @@ impl PriceOracleContract {
 
     // Record new price feed history snapshot. Can be invoked only by the admin account.
     //
+    // All asset updates are validated before anything is stored. By default, an invalid asset update reverts the
+    // whole snapshot. When skipping invalid updates is enabled, invalid asset updates are marked as rejected and
+    // reported with events (including the exact yield rate deviation), and only the valid ones are stored.
+    //
     // # Arguments
     //
     // * `admin` - Admin account address
     // * `updates` - Price feed snapshot
     // * `timestamp` - History snapshot timestamp
     //
+    // # Returns
+    //
+    // Update outcome for each asset, in the same order as the updates
+    //
     // # Panics
     //
-    // Panics if the caller doesn't match admin address, or if the price snapshot record is invalid
-    pub fn set_price(e: Env, updates: Vec<i128>, timestamp: u64) {
+    // Panics if the caller doesn't match admin address, or if the price snapshot record is invalid.
+    // Invalid asset updates panic too, unless skipping invalid updates is enabled
+    pub fn set_price(e: Env, updates: Vec<i128>, timestamp: u64) -> Vec<UpdateResult> {
         e.panic_if_not_admin();
-        let updates_len = updates.len();
-        if updates_len == 0 || updates_len >= 256 {
-            panic_with_error!(&e, Error::InvalidUpdateLength);
+        let fxs = match validate_updates(&e, &updates, timestamp) {
+            Ok(fxs) => fxs,
+            Err(error) => panic_with_error!(&e, error),
+        };
+
+        let ledgers_to_live = get_ledgers_to_live(&e);
+
+        //validate all the updates before storing anything
+        let (results, validated) = validate_snapshot(&e, &updates, &fxs, timestamp);
+        let skip_invalid = e.get_skip_invalid_updates();
+        for (i, result) in results.iter().enumerate() {
+            if let UpdateResult::Rejected(error_code) = result {
+                //revert the whole snapshot unless invalid updates can be skipped
+                if !skip_invalid {
+                    panic_with_error!(&e, soroban_sdk::Error::from_contract_error(error_code));
+                }
+                e.reject_price(i as u8, updates.get_unchecked(i as u32), error_code, timestamp, ledgers_to_live);
+            }
+        }
+
+        //store the valid updates
+        let mut stored = false;
+        for (i, validated_price) in validated.iter().enumerate() {
+            if let Some(validated_price) = validated_price {
+                let i = i as u32;
+                e.store_price(
+                    i as u8,
+                    fxs.get_unchecked(i),
+                    updates.get_unchecked(i),
+                    validated_price,
+                    timestamp,
+                    ledgers_to_live,
+                );
+                stored = true;
+            }
         }
-        let timeframe: u64 = e.get_resolution().into();
-        let ledger_timestamp = now(&e);
-        if timestamp == 0
-            || !timestamp.is_valid_timestamp(timeframe)
-            || timestamp > ledger_timestamp
-        {
-            panic_with_error!(&e, Error::InvalidTimestamp);
+        //the snapshot timestamp only moves forward if something was stored
+        if stored {
+            e.set_last_timestamp(timestamp);
         }
+        results
+    }
 
-        let retention_period = e.get_retention_period();
+    // Corrects the yield rate of an already published record. Can be invoked only by the admin account.
+    // The superseded yield rate is kept and a correction event is published for auditing.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to correct
+    // * `timestamp` - Timestamp of the published record in seconds
+    // * `yield_rate` - Corrected yield rate
+    // * `reason` - Reason for the correction
+    //
+    // # Returns
+    //
+    // Corrected price (in contracts' base asset and decimals)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the asset is not supported, if there is no record
+    // at the timestamp, if the record was already corrected, or if the corrected yield rate or price is invalid
+    // relative to the adjacent records or the price band
+    pub fn correct_price(e: Env, asset: Asset, timestamp: u64, yield_rate: i128, reason: String) -> i128 {
+        e.panic_if_not_admin();
+        let asset_index = e
+            .get_asset_index(&asset)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::AssetMissing));
+        let timestamp = timestamp
+            .checked_mul(1000)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::InvalidTimestamp))
+            .get_normalized_timestamp(e.get_resolution().into());
+        let fx = e.get_fxs().get(asset_index as u32).unwrap();
+        let ledgers_to_live = get_ledgers_to_live(&e);
+        let (price, superseded_rate) =
+            match e.correct_price(asset_index, fx, yield_rate, timestamp, ledgers_to_live) {
+                Ok(val) => val,
+                Err(error) => panic_with_error!(&e, error),
+            };
+        e.events().publish(
+            (Symbol::new(&e, "price_corrected"), asset_index as u32),
+            (timestamp, superseded_rate, yield_rate, reason),
+        );
+        price
+    }
 
-        let ledgers_to_live: u32 = ((retention_period / 1000 / 5) + 1) as u32;
+    // Writes historical records for an asset, e.g. to bootstrap the history of a newly added asset.
+    // Can be invoked only by the admin account. Supplied fx prices are used instead of the fx oracle and are stored
+    // as is, so they must already be in the contract decimals and quoted in the contract base asset (the fx oracle
+    // decimals and base are not applied).
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to backfill
+    // * `records` - Historical records as (timestamp in seconds, yield rate, fx price in contract decimals and base
+    //   asset) tuples, ordered by timestamp
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, if the asset is not supported, if any timestamp is outside
+    // of the retention period or already has a record, or if the supplied yield rates or prices are inconsistent
+    // or outside of the price band
+    pub fn backfill(e: Env, asset: Asset, records: Vec<(u64, i128, i128)>) {
+        e.panic_if_not_admin();
+        let asset_index = e
+            .get_asset_index(&asset)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::AssetMissing));
+        let mut normalized_records = Vec::new(&e);
+        for (timestamp, yield_rate, fx_price) in records.iter() {
+            let timestamp = timestamp //convert to milliseconds
+                .checked_mul(1000)
+                .unwrap_or_else(|| panic_with_error!(&e, Error::InvalidTimestamp));
+            normalized_records.push_back((timestamp, yield_rate, fx_price));
+        }
+        if let Err(error) = e.backfill_prices(asset_index, &normalized_records) {
+            panic_with_error!(&e, error);
+        }
+    }
 
-        //get the last timestamp
-        let last_timestamp = e.get_last_timestamp();
+    // Returns the yield rate superseded by the last correction of a record.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to look up
+    // * `timestamp` - Record timestamp
+    //
+    // # Returns
+    //
+    // Superseded yield rate or None if the record was not corrected or the asset is not supported
+    pub fn superseded_yield_rate(e: Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
+        let normalized_timestamp = //convert to milliseconds and normalize
+            (timestamp * 1000).get_normalized_timestamp(e.get_resolution().into());
+        let asset_index = e.get_asset_index(&asset)?;
+        let yield_rate = e.get_superseded_yield_rate(asset_index, normalized_timestamp)?;
+        Some(get_normalized_price_data(yield_rate, normalized_timestamp))
+    }
 
-        //validate array lengths match
-        let fxs = e.get_fxs();
-        let assets = e.get_assets();
-        if fxs.len() != updates_len || updates_len != assets.len() {
-            panic_with_error!(&e, Error::FxArrayLengthMismatch);
+    // Runs all price update checks without storing anything, so publishers can verify an update before submitting it.
+    //
+    // # Arguments
+    //
+    // * `updates` - Price feed snapshot
+    // * `timestamp` - History snapshot timestamp
+    //
+    // # Returns
+    //
+    // Update result each asset would get, in the same order as the updates, or the snapshot error if the snapshot
+    // itself is invalid (length, timestamp or assets mismatch). Rejected updates are also reported with the same
+    // events set_price publishes when skipping them, so a simulation shows the exact yield rate deviation
+    pub fn validate_prices(e: Env, updates: Vec<i128>, timestamp: u64) -> Result<Vec<UpdateResult>, Error> {
+        let fxs = validate_updates(&e, &updates, timestamp)?;
+        let (results, _) = validate_snapshot(&e, &updates, &fxs, timestamp);
+        for (i, result) in results.iter().enumerate() {
+            if let UpdateResult::Rejected(error_code) = result {
+                e.publish_rejection(i as u8, updates.get_unchecked(i as u32), error_code, timestamp);
+            }
         }
+        Ok(results)
+    }
 
-        //iterate over the updates
-        for (i, price) in updates.iter().enumerate() {
-            //don't store zero prices
-            if price == 0 {
-                continue;
-            }
-            let asset = i as u8;
-            let fx = fxs.get(i as u32).unwrap();
+    // Enables or disables skipping of invalid asset updates. Can be invoked only by the admin account.
+    // When enabled, assets failing validation are rejected individually instead of reverting the whole update.
+    //
+    // # Arguments
+    //
+    // * `enabled` - Whether invalid asset updates should be skipped
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address
+    pub fn set_skip_invalid_updates(e: Env, enabled: bool) {
+        e.panic_if_not_admin();
+        e.set_skip_invalid_updates(enabled);
+    }
 
-            //store the new price
-            e.set_price(asset, fx, price, timestamp, ledgers_to_live);
-        }
-        if timestamp > last_timestamp {
-            e.set_last_timestamp(timestamp);
+    // Returns whether invalid asset updates are skipped instead of reverting the whole update.
+    //
+    // # Returns
+    //
+    // True if invalid asset updates are skipped
+    pub fn skip_invalid_updates(e: Env) -> bool {
+        e.get_skip_invalid_updates()
+    }
+
+    // Returns the reason an asset update was rejected at the given timestamp.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to look up
+    // * `timestamp` - Update timestamp
+    //
+    // # Returns
+    //
+    // Rejection error code or None if the update was not rejected or the asset is not supported
+    pub fn rejection(e: Env, asset: Asset, timestamp: u64) -> Option<u32> {
+        let normalized_timestamp = //convert to milliseconds and normalize
+            (timestamp * 1000).get_normalized_timestamp(e.get_resolution().into());
+        let asset = e.get_asset_index(&asset)?;
+        //the update could be resubmitted after the snapshot was rejected
+        if e.get_price(asset, normalized_timestamp).is_some() {
+            return None;
         }
+        e.get_rejection(asset, normalized_timestamp)
+    }
+
+    // Sets the number of decimal places used to represent annualized rates. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `decimals` - Number of decimal places for annualized rates (in basis points)
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address
+    pub fn set_apr_decimals(e: Env, decimals: u32) {
+        e.panic_if_not_admin();
+        e.set_apr_decimals(decimals);
+    }
+
+    // Sets the APR used to project the price of an asset between records. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `asset` - Asset to configure
+    // * `apr` - APR in basis points
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address, or if the asset is not supported
+    pub fn set_asset_apr(e: Env, asset: Asset, apr: u32) {
+        e.panic_if_not_admin();
+        let asset_index = e
+            .get_asset_index(&asset)
+            .unwrap_or_else(|| panic_with_error!(&e, Error::AssetMissing));
+        e.set_asset_apr(asset_index, apr);
     }
 
     // Updates the contract source code. Can be invoked only by the admin account.
@@ -482,6 +1457,131 @@ fn prices<F: Fn(u64) -> Option<PriceData>>(
     }
 }
 
+fn validate_snapshot(
+    e: &Env,
+    updates: &Vec<i128>,
+    fxs: &Vec<Symbol>,
+    timestamp: u64,
+) -> (Vec<UpdateResult>, Vec<Option<ValidatedPrice>>) {
+    let mut results = Vec::new(e);
+    let mut validated = Vec::new(e);
+    for (i, yield_rate) in updates.iter().enumerate() {
+        //don't store zero prices
+        if yield_rate == 0 {
+            results.push_back(UpdateResult::Skipped);
+            validated.push_back(None);
+            continue;
+        }
+        match e.validate_price(i as u8, &fxs.get_unchecked(i as u32), yield_rate, timestamp) {
+            Ok(validated_price) => {
+                results.push_back(UpdateResult::Updated(validated_price.0));
+                validated.push_back(Some(validated_price));
+            }
+            Err(error) => {
+                results.push_back(UpdateResult::Rejected(error as u32));
+                validated.push_back(None);
+            }
+        }
+    }
+    (results, validated)
+}
+
+fn validate_updates(e: &Env, updates: &Vec<i128>, timestamp: u64) -> Result<Vec<Symbol>, Error> {
+    let updates_len = updates.len();
+    if updates_len == 0 || updates_len >= 256 {
+        return Err(Error::InvalidUpdateLength);
+    }
+    let timeframe: u64 = e.get_resolution().into();
+    let ledger_timestamp = now(e);
+    if timestamp == 0
+        || !timestamp.is_valid_timestamp(timeframe)
+        || timestamp > ledger_timestamp
+    {
+        return Err(Error::InvalidTimestamp);
+    }
+    //published records are append-only, use correct_price to fix them
+    if timestamp <= e.get_last_timestamp() {
+        return Err(Error::TimestampNotIncreasing);
+    }
+
+    //validate array lengths match
+    let fxs = e.get_fxs();
+    let assets = e.get_assets();
+    if fxs.len() != updates_len || updates_len != assets.len() {
+        return Err(Error::FxArrayLengthMismatch);
+    }
+    Ok(fxs)
+}
+
+//the number of records retained by the fx oracle, or u32::MAX if it keeps the whole history
+fn get_fx_oracle_max_records(e: &Env) -> u32 {
+    let fx_oracle_address = e
+        .get_fx_oracle_address()
+        .unwrap_or_else(|| panic_with_error!(e, Error::FxOracleUnavailable));
+    let period = match PriceOracleContractClient::new(e, &fx_oracle_address).try_period() {
+        Ok(Ok(period)) => period,
+        _ => panic_with_error!(e, Error::FxOracleUnavailable),
+    };
+    let fx_resolution = e.get_fx_resolution() as u64 / 1000; //convert to seconds
+    match period {
+        Some(period) if fx_resolution > 0 => (period / fx_resolution).try_into().unwrap_or(u32::MAX),
+        _ => u32::MAX,
+    }
+}
+
+fn set_fx_oracle(e: &Env, fx_oracle_address: &Address) {
+    //the fx oracle must quote prices in the contract's base asset or quote the contract's base asset
+    let fx_oracle = PriceOracleContractClient::new(e, fx_oracle_address);
+    let (fx_base, fx_decimals, fx_resolution) =
+        match (fx_oracle.try_base(), fx_oracle.try_decimals(), fx_oracle.try_resolution()) {
+            (Ok(Ok(fx_base)), Ok(Ok(fx_decimals)), Ok(Ok(fx_resolution))) => (fx_base, fx_decimals, fx_resolution),
+            _ => panic_with_error!(e, Error::FxOracleUnavailable),
+        };
+    //a different base is supported for currencies quoted by the fx oracle, prices are converted through its fx leg
+    if fx_base != e.get_base_asset() {
+        let is_quoted = match e.get_base_asset() {
+            Asset::Other(base_symbol) => matches!(fx_oracle.try_lastprice(&Asset::Other(base_symbol)), Ok(Ok(Some(_)))),
+            Asset::Stellar(_) => false,
+        };
+        if !is_quoted {
+            panic_with_error!(e, Error::FxBaseMismatch);
+        }
+    }
+    e.set_fx_oracle_address(fx_oracle_address);
+    e.set_fx_base(&fx_base);
+    e.set_fx_decimals(fx_decimals);
+    e.set_fx_resolution(fx_resolution * 1000); //convert to milliseconds
+}
+
+fn get_ledgers_to_live(e: &Env) -> u32 {
+    let retention_period = e.get_retention_period();
+    ((retention_period / 1000 / 5) + 1) as u32
+}
+
+fn rescale_price(e: &Env, price: i128, decimals: u32, rounding: Rounding) -> i128 {
+    match price.rescale(e.get_decimals(), decimals, rounding) {
+        Some(price) => price,
+        None => panic_with_error!(e, Error::IntegerOverflow),
+    }
+}
+
+fn rescale_price_data(e: &Env, price_data: PriceData, decimals: u32, rounding: Rounding) -> PriceData {
+    PriceData {
+        price: rescale_price(e, price_data.price, decimals, rounding),
+        timestamp: price_data.timestamp,
+    }
+}
+
+fn obtain_record_timestamp_checked(e: &Env) -> Result<u64, Error> {
+    if e.get_last_timestamp() == 0 {
+        return Err(Error::PriceNotFound);
+    }
+    match e.obtain_record_timestamp() {
+        0 => Err(Error::StalePrice),
+        timestamp => Ok(timestamp),
+    }
+}
+
 fn now(e: &Env) -> u64 {
     e.ledger().timestamp() * 1000 //convert to milliseconds
 }
@@ -491,23 +1591,41 @@ fn get_twap<F: Fn(u64) -> Option<PriceData>>(
     get_price_fn: F,
     records: u32,
 ) -> Option<i128> {
-    let prices = prices(&e, get_price_fn, records)?;
+    get_twap_checked(e, get_price_fn, records).ok()
+}
+
+fn get_twap_checked<F: Fn(u64) -> Option<PriceData>>(
+    e: &Env,
+    get_price_fn: F,
+    records: u32,
+) -> Result<i128, Error> {
+    obtain_record_timestamp_checked(e)?;
+    let prices = prices(&e, get_price_fn, records).ok_or(Error::PriceNotFound)?;
 
     if prices.len() != records {
-        return None;
+        return Err(Error::PriceNotFound);
     }
 
-    let last_price_timestamp = prices.first()?.timestamp * 1000; //convert to milliseconds to match the timestamp format
+    let last_price_timestamp = prices.first().ok_or(Error::PriceNotFound)?.timestamp * 1000; //convert to milliseconds to match the timestamp format
     let timeframe = e.get_resolution() as u64;
     let current_time = now(&e);
 
     //check if the last price is too old
     if last_price_timestamp + timeframe + 60 * 1000 < current_time {
-        return None;
+        return Err(Error::StalePrice);
     }
 
-    let sum: i128 = prices.iter().map(|price_data| price_data.price).sum();
-    Some(sum / prices.len() as i128)
+    //average the quotients and remainders separately, so the sum of the prices can't overflow
+    let count = prices.len() as i128;
+    let mut average: i128 = 0;
+    let mut remainders: i128 = 0;
+    for price_data in prices.iter() {
+        average = mul_div(price_data.price, 1, count, Rounding::Floor)
+            .and_then(|val| average.checked_add(val))
+            .ok_or(Error::IntegerOverflow)?;
+        remainders += price_data.price.rem_euclid(count);
+    }
+    average.checked_add(remainders / count).ok_or(Error::IntegerOverflow)
 }
 
 fn get_x_price(
@@ -530,31 +1648,29 @@ fn get_x_price_by_indexes(
     timestamp: u64,
     decimals: u32,
 ) -> Option<PriceData> {
+    //no price if it can't be calculated, e.g. the quote asset price is zero
+    get_x_price_by_indexes_checked(e, asset_pair_indexes, timestamp, decimals).ok()
+}
+
+fn get_x_price_by_indexes_checked(
+    e: &Env,
+    asset_pair_indexes: (u8, u8),
+    timestamp: u64,
+    decimals: u32,
+) -> Result<PriceData, Error> {
     let (base_asset, quote_asset) = asset_pair_indexes;
     //check if the asset are the same
     if base_asset == quote_asset {
-        return Some(get_normalized_price_data(10i128.pow(decimals), timestamp));
+        return Ok(get_normalized_price_data(10i128.pow(decimals), timestamp));
     }
 
-    //get the price for base_asset
-    let base_asset_price = e.get_price(base_asset, timestamp);
-    if base_asset_price.is_none() {
-        return None;
-    }
-
-    //get the price for quote_asset
-    let quote_asset_price = e.get_price(quote_asset, timestamp);
-    if quote_asset_price.is_none() {
-        return None;
-    }
+    //get the prices for base_asset and quote_asset
+    let base_asset_price = e.get_price(base_asset, timestamp).ok_or(Error::PriceNotFound)?;
+    let quote_asset_price = e.get_price(quote_asset, timestamp).ok_or(Error::PriceNotFound)?;
 
     //calculate the cross price
-    Some(get_normalized_price_data(
-        base_asset_price
-            .unwrap()
-            .fixed_div_floor(quote_asset_price.unwrap(), decimals),
-        timestamp,
-    ))
+    let cross_price = base_asset_price.fixed_div_floor(quote_asset_price, decimals)?;
+    Ok(get_normalized_price_data(cross_price, timestamp))
 }
 
 fn get_asset_pair_indexes(e: &Env, base_asset: Asset, quote_asset: Asset) -> Option<(u8, u8)> {
@@ -579,6 +1695,30 @@ fn get_price_data(e: &Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
     get_price_data_by_index(e, asset.unwrap(), timestamp)
 }
 
+fn get_price_data_in(e: &Env, asset: Asset, fx: Symbol, timestamp: u64) -> Option<PriceData> {
+    let price_data = get_price_data(e, asset, timestamp)?;
+
+    //get the fx price used for the tick
+    let fx_price = e.obtain_fx_price(&fx, timestamp)?;
+
+    //calculate the price in fx currency
+    let price = price_data.price.fixed_div_floor(fx_price, e.get_decimals()).ok()?;
+    Some(get_normalized_price_data(price, timestamp))
+}
+
+fn get_prices_data(e: &Env, assets: Vec<Asset>, timestamp: u64) -> Vec<Option<PriceData>> {
+    let mut result = Vec::new(e);
+    for asset in assets.iter() {
+        //no records for the zero timestamp
+        if timestamp == 0 {
+            result.push_back(None);
+            continue;
+        }
+        result.push_back(get_price_data(e, asset, timestamp));
+    }
+    result
+}
+
 fn get_price_data_by_index(e: &Env, asset: u8, timestamp: u64) -> Option<PriceData> {
     let price = e.get_price(asset, timestamp);
     if price.is_none() {
@@ -587,6 +1727,99 @@ fn get_price_data_by_index(e: &Env, asset: u8, timestamp: u64) -> Option<PriceDa
     Some(get_normalized_price_data(price.unwrap(), timestamp))
 }
 
+fn get_apr(e: &Env, asset: u8, timestamp: u64, lookback: u64) -> Option<i128> {
+    let current_rate = e.get_last_yield_rate(asset, timestamp)?;
+
+    //find the yield rate at the start of the lookback period
+    let resolution = e.get_resolution() as u64;
+    let previous_timestamp = timestamp
+        .checked_sub(lookback)?
+        .get_normalized_timestamp(resolution);
+    if previous_timestamp == 0 || previous_timestamp >= timestamp {
+        return None;
+    }
+    let previous_rate = e.get_last_yield_rate(asset, previous_timestamp)?;
+
+    //annualize the growth of the yield rate in basis points in a single step, so the precision doesn't depend on
+    //the price decimals: (current - previous) * 10_000 * year / (previous * elapsed)
+    let elapsed = (timestamp - previous_timestamp) as i128;
+    let scale = 10i128.checked_pow(e.get_apr_decimals())?;
+    mul_div(
+        current_rate.checked_sub(previous_rate)?,
+        scale.checked_mul(10_000)?.checked_mul(YEAR_MS as i128)?,
+        previous_rate.checked_mul(elapsed)?,
+        Rounding::Floor,
+    )
+}
+
+fn get_projected_price_data(
+    e: &Env,
+    asset: u8,
+    last_timestamp: u64,
+    timestamp: u64,
+) -> Option<ProjectedPriceData> {
+    let resolution = e.get_resolution() as u64;
+
+    //return the recorded price for the timestamps covered by the records
+    if timestamp <= last_timestamp {
+        let normalized_timestamp = timestamp.get_normalized_timestamp(resolution);
+        let price = e.get_price(asset, normalized_timestamp)?;
+        return Some(ProjectedPriceData {
+            price,
+            timestamp: normalized_timestamp / 1000, //convert to seconds
+            projected: false,
+        });
+    }
+
+    //check if the timestamp is too far from the last record
+    let elapsed = timestamp - last_timestamp;
+    if elapsed > resolution * 2 {
+        return None;
+    }
+
+    let price = e.get_price(asset, last_timestamp)?;
+    let yield_rate = e.get_last_yield_rate(asset, last_timestamp)?;
+    let growth = match e.get_asset_apr(asset) {
+        //accrue the configured APR: yield_rate * apr * elapsed / (10_000 * year)
+        Some(apr) => mul_div(
+            yield_rate,
+            (apr as i128).checked_mul(elapsed as i128)?,
+            10_000 * YEAR_MS as i128,
+            Rounding::Floor,
+        )?,
+        //extrapolate the growth between the last two records, ignoring decreases
+        None => {
+            let previous_yield_rate =
+                e.get_last_yield_rate(asset, last_timestamp.checked_sub(resolution)?);
+            if previous_yield_rate.is_none() {
+                //not enough data to project the price, return the last record as is
+                return Some(ProjectedPriceData {
+                    price,
+                    timestamp: last_timestamp / 1000, //convert to seconds
+                    projected: false,
+                });
+            }
+            (yield_rate - previous_yield_rate.unwrap())
+                .max(0)
+                .checked_mul(elapsed as i128)?
+                .checked_div(resolution as i128)?
+        }
+    };
+
+    //scale the last price by the projected yield rate growth
+    let projected_price = mul_div(price, yield_rate.checked_add(growth)?, yield_rate, Rounding::Floor)?;
+    Some(ProjectedPriceData {
+        price: projected_price,
+        timestamp: timestamp / 1000, //convert to seconds
+        projected: true,
+    })
+}
+
+fn get_yield_rate_data_by_index(e: &Env, asset: u8, timestamp: u64) -> Option<PriceData> {
+    let yield_rate = e.get_last_yield_rate(asset, timestamp)?;
+    Some(get_normalized_price_data(yield_rate, timestamp))
+}
+
 fn get_normalized_price_data(price: i128, timestamp: u64) -> PriceData {
     PriceData {
         price,
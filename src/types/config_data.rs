@@ -15,10 +15,14 @@ pub struct ConfigData {
     pub base_asset: Asset,
     // The number of decimals for the prices.
     pub decimals: u32,
+    // The number of decimals for the published yield rates.
+    pub yield_decimals: u32,
     // The resolution of the prices.
     pub resolution: u32,
-    // The FX oracle contract address (immutable after initialization).
+    // The FX oracle contract address (can be replaced by the admin with set_fx_oracle_address).
     pub fx_oracle_address: Address,
-    // Maximum allowed yield rate increase as a percentage (e.g., 1 = 1%, 10 = 10%)
-    pub max_yield_deviation_percent: u32,
+    // Maximum allowed yield rate increase in basis points (e.g., 100 = 1%, 1000 = 10%)
+    pub max_yield_increase_bps: u32,
+    // Maximum allowed yield rate decrease in basis points (e.g., 100 = 1%)
+    pub max_yield_decrease_bps: u32,
 }
@@ -1,7 +1,7 @@
 use soroban_sdk::contracterror;
 
 #[contracterror]
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 // The error codes for the contract.
 pub enum Error {
     // The contract is already initialized.
@@ -28,7 +28,7 @@ pub enum Error {
     StaleFxPrice = 10,
     // The assets and fxs arrays have mismatched lengths
     FxArrayLengthMismatch = 11,
-    // The yield rate is invalid (must be >= 1.0 with matching decimals)
+    // The yield rate is invalid (must be within the asset's validation policy bounds in yield decimals, >= 1.0 by default)
     InvalidYieldRate = 12,
     // The fx price is invalid (must be positive and non-zero)
     InvalidFxPrice = 13,
@@ -42,4 +42,44 @@ pub enum Error {
     YieldRateDecreased = 17,
     // The yield rate increased by more than the maximum allowed deviation
     YieldRateDeviationExceeded = 18,
+    // The validation policy is invalid (yield rate bounds must be positive and ordered)
+    InvalidValidationPolicy = 19,
+    // The prices timestamp is not greater than the last stored timestamp (published records are append-only)
+    TimestampNotIncreasing = 20,
+    // There is no published record to correct at the given timestamp
+    RecordMissing = 21,
+    // There is already a published record at the given timestamp
+    RecordAlreadyExists = 22,
+    // The final price is outside of the asset's price band
+    PriceOutOfBand = 23,
+    // The final price changed by more than the asset's price band allows
+    PriceDeviationExceeded = 24,
+    // The price band is invalid (bounds must be non-negative and ordered)
+    InvalidPriceBand = 25,
+    // The fx price changed by more than the fx guard allows
+    FxDeviationExceeded = 26,
+    // The fx is not in the contract's list of supported fxs
+    FxMissing = 27,
+    // The fx oracle base asset doesn't match the contract's base asset
+    FxBaseMismatch = 28,
+    // The fx mode is invalid (TWAP requires at least one record)
+    InvalidFxMode = 29,
+    // The fx route is invalid (too long, contains loops or legs not quoted by the fx oracle)
+    InvalidFxRoute = 30,
+    // The fx source is invalid (rates must be positive and manual rates must not be expired)
+    InvalidFxSource = 31,
+    // The divisor of a price calculation is zero
+    DivisionByZero = 32,
+    // A price used in a price calculation is negative
+    NegativePrice = 33,
+    // The asset is not supported by the contract
+    AssetNotSupported = 34,
+    // There is no price record for the asset at the requested timestamp
+    PriceNotFound = 35,
+    // The most recent price record is too old to be used
+    StalePrice = 36,
+    // The final price is not positive (the yield rate or fx price is too small to be represented)
+    NonPositivePrice = 37,
+    // The record was already corrected once (only the originally published yield rate is kept for auditing)
+    RecordAlreadyCorrected = 38,
 }
@@ -0,0 +1,11 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The fx price change limit for an fx symbol.
+pub struct FxGuard {
+    // Maximum allowed fx price change between ticks in basis points.
+    pub max_change_bps: u32,
+    // Whether to use the previous fx price instead of rejecting the update when the limit is exceeded.
+    pub clamp: bool,
+}
@@ -0,0 +1,11 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The source of the fx price for an fx symbol.
+pub enum FxMode {
+    // The last fx oracle price.
+    Spot,
+    // The fx oracle TWAP over the given number of records.
+    Twap(u32),
+}
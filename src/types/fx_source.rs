@@ -0,0 +1,14 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The source of the fx rate for an fx symbol.
+pub enum FxSource {
+    // The FX oracle feed.
+    Reflector,
+    // A constant rate in the FX oracle base currency (with contract decimals).
+    Peg(i128),
+    // An admin-set rate in the FX oracle base currency (with contract decimals), valid until the expiration
+    // timestamp (in seconds).
+    Manual(i128, u64),
+}
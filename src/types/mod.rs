@@ -2,4 +2,12 @@ pub mod asset;
 pub mod asset_type;
 pub mod config_data;
 pub mod error;
+pub mod fx_guard;
+pub mod fx_mode;
+pub mod fx_source;
+pub mod price_band;
 pub mod price_data;
+pub mod projected_price_data;
+pub mod rounding;
+pub mod update_result;
+pub mod validation_policy;
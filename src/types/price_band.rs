@@ -0,0 +1,13 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The final price sanity limits for an asset, covering both the yield rate and the fx leg.
+pub struct PriceBand {
+    // Minimum allowed final price in contract decimals.
+    pub min_price: i128,
+    // Maximum allowed final price in contract decimals.
+    pub max_price: i128,
+    // Maximum allowed final price change between records in basis points (0 to disable).
+    pub max_change_bps: u32,
+}
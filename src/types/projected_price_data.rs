@@ -0,0 +1,13 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The price data for an asset projected from the most recent record.
+pub struct ProjectedPriceData {
+    // The price in contracts' base asset and decimals.
+    pub price: i128,
+    // The timestamp of the price.
+    pub timestamp: u64,
+    // Whether the price was extrapolated beyond the most recent record.
+    pub projected: bool,
+}
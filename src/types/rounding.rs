@@ -0,0 +1,15 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+// The rounding mode used when a price calculation drops precision.
+pub enum Rounding {
+    // Round towards negative infinity.
+    Floor,
+    // Round towards positive infinity.
+    Ceil,
+    // Round to the nearest value, ties away from zero.
+    HalfUp,
+    // Round to the nearest value, ties to the even one.
+    HalfEven,
+}
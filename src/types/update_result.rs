@@ -0,0 +1,13 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The outcome of a price update for an asset.
+pub enum UpdateResult {
+    // The price was stored (in contracts' base asset and decimals).
+    Updated(i128),
+    // The update was empty (zero yield rate), so nothing was stored.
+    Skipped,
+    // The update failed validation and was not stored (contains the error code).
+    Rejected(u32),
+}
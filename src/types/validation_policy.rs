@@ -0,0 +1,20 @@
+use soroban_sdk::contracttype;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+// The yield rate validation parameters for an asset.
+pub struct ValidationPolicy {
+    // Maximum allowed yield rate increase between records in basis points.
+    pub max_increase_bps: u32,
+    // Maximum allowed yield rate decrease between records in basis points.
+    pub max_decrease_bps: u32,
+    // Minimum allowed yield rate in yield decimals.
+    pub min_yield_rate: i128,
+    // Maximum allowed yield rate in yield decimals.
+    pub max_yield_rate: i128,
+    // Whether the yield rate decrease limit applies.
+    pub monotonic: bool,
+    // Maximum allowed yield rate growth as an APR in basis points, scaled by the time elapsed since the
+    // previous record (0 to disable).
+    pub max_apr_bps: u32,
+}